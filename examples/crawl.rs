@@ -0,0 +1,63 @@
+//! Crawl the pages of one capsule politely, printing the status of each.
+//!
+//! ```sh
+//! cargo run --example crawl -- gemini://geminiprotocol.net/ 20
+//! ```
+
+use std::{collections::HashSet, time::Duration};
+use yagc::{ClientBuilder, PolitenessPolicy, Priority, Response, URL};
+
+/// Get the targets of the link lines of a gemtext page, resolved against the page's URL.
+fn links(page: &URL, response: &Response) -> Vec<URL> {
+    let Ok(text) = response.text_lossy() else {
+        return Vec::new();
+    };
+
+    text.lines()
+        .filter_map(|line| line.strip_prefix("=>"))
+        .filter_map(|line| line.split_whitespace().next())
+        .filter_map(|target| page.join(target).ok())
+        .collect()
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let start = URL::try_from(args.next().expect("usage: crawl <url> [max pages]").as_str()).expect("invalid URL");
+    let max_pages: usize = args.next().map_or(20, |max| max.parse().expect("invalid page count"));
+
+    let client = ClientBuilder::new()
+        .politeness_policy(PolitenessPolicy { min_delay: Duration::from_millis(500), max_concurrent_requests: 2 })
+        .build()
+        .expect("failed to load the TOFU store");
+
+    let mut seen = HashSet::from([start.to_string()]);
+    let mut queue = vec![start.clone()];
+    let mut fetched = 0;
+
+    while !queue.is_empty() && fetched < max_pages {
+        let batch: Vec<URL> = queue.drain(..queue.len().min(max_pages - fetched)).collect();
+        let results = Priority::Background.scope(client.fetch_all(&batch, 2)).await;
+        fetched += batch.len();
+
+        for (url, result) in batch.iter().zip(results) {
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    println!("ERR {url}: {e:?}");
+                    continue;
+                },
+            };
+            println!("{} {url}", response.status_code());
+
+            // stay on the capsule we started on
+            for link in links(url, &response) {
+                if link.host == start.host && seen.insert(link.to_string()) {
+                    queue.push(link);
+                }
+            }
+        }
+    }
+
+    println!("fetched {fetched} pages, {} more found", queue.len());
+}
@@ -0,0 +1,34 @@
+//! Fetch a page and print it.
+//!
+//! ```sh
+//! cargo run --example fetch -- gemini://geminiprotocol.net/
+//! ```
+
+use yagc::{Client, Response, URL};
+
+#[tokio::main]
+async fn main() {
+    let url = std::env::args().nth(1).unwrap_or("gemini://geminiprotocol.net/".to_string());
+    let url = URL::try_from(url.as_str()).expect("invalid URL");
+
+    let client = Client::new();
+    let result = match client.fetch_with_chain(&url).await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("failed to fetch {url}: {e:?}");
+            std::process::exit(1);
+        },
+    };
+
+    for redirect in &result.chain {
+        eprintln!("redirected from {redirect}");
+    }
+
+    match &result.response {
+        Response::Success { body_mime_type, .. } => {
+            eprintln!("{} ({body_mime_type})", result.url);
+            println!("{}", result.response.text_lossy().unwrap());
+        },
+        response => eprintln!("{} responded with status {}: {response:?}", result.url, response.status_code()),
+    }
+}
@@ -0,0 +1,32 @@
+//! Fetch a page with a client certificate, presented only within the page's directory on its host.
+//!
+//! ```sh
+//! cargo run --example identity -- gemini://example.com/account/ cert.pem key.pem
+//! ```
+
+use yagc::{Client, Identity, URL};
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(url), Some(certificate_path), Some(key_path)) = (args.next(), args.next(), args.next()) else {
+        panic!("usage: identity <url> <certificate.pem> <key.pem>");
+    };
+    let url = URL::try_from(url.as_str()).expect("invalid URL");
+
+    let certificate_pem = std::fs::read(&certificate_path).expect("failed to read the certificate");
+    let key_pem = std::fs::read(&key_path).expect("failed to read the key");
+    let identity = Identity::from_pem(certificate_pem, key_pem).expect("invalid identity");
+
+    let client = Client::new();
+    let host = url.host.as_ref().expect("the URL has no host");
+    let directory = url.path.rsplit_once('/').map_or("/", |(directory, _)| directory);
+    client.add_identity(host, directory, identity).expect("failed to add the identity");
+
+    let response = client.fetch(&url).await.expect("failed to fetch");
+
+    match response.text_lossy() {
+        Ok(text) => println!("{text}"),
+        Err(_) => println!("{response:?}"),
+    }
+}
@@ -0,0 +1,38 @@
+//! Fetch a page, asking on the terminal whenever the server wants input.
+//!
+//! ```sh
+//! cargo run --example input -- gemini://example.com/search
+//! ```
+
+use std::io::Write;
+use yagc::{Client, InputPrompt, URL};
+
+/// Ask the user for input on the terminal, or return `None` if they enter nothing.
+fn ask(prompt: &InputPrompt) -> Option<String> {
+    let note = if prompt.sensitive { " (sensitive)" } else { "" };
+    print!("{}{note}: ", prompt.prompt);
+    std::io::stdout().flush().ok()?;
+
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).ok()?;
+    let line = line.trim_end_matches(['\r', '\n']);
+
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+#[tokio::main]
+async fn main() {
+    let url = std::env::args().nth(1).expect("usage: input <url>");
+    let url = URL::try_from(url.as_str()).expect("invalid URL");
+
+    let client = Client::new();
+    let response = client
+        .fetch_with_input(&url, |prompt| std::future::ready(ask(&prompt)))
+        .await
+        .expect("failed to fetch");
+
+    match response.text_lossy() {
+        Ok(text) => println!("{text}"),
+        Err(_) => println!("{response:?}"),
+    }
+}
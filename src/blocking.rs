@@ -0,0 +1,208 @@
+//! A blocking Gemini client, for programs that don't use async.
+//!
+//! The blocking client wraps the async `Client` and drives it on its own single-threaded tokio runtime.
+//! Its methods must not be called from within an async runtime.
+
+use crate::{
+    BatchJob, ChangeCheck, ClientBuilder, ClientMetrics, ClientError, Diagnosis, FetchResult, FingerprintStatus, Host, HostStats, Identity, InputPrompt, Priority, RecordedFingerprint, Request, Response,
+    TlsConnection, URL,
+};
+use std::{path::Path, time::Duration};
+use tokio::runtime::{Builder, Runtime};
+
+/// A blocking client for the Gemini protocol. Build one with `ClientBuilder::build_blocking`.
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+impl ClientBuilder {
+    /// Build a blocking client, loading its TOFU store.
+    pub fn build_blocking(&self) -> Result<Client, ClientError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ClientError::FailedToStartRuntime(e.to_string()))?;
+
+        Ok(Client { inner: self.build()?, runtime })
+    }
+}
+
+impl Client {
+    /// Create a new blocking client with a TOFU store loaded from the default path, falling back to an in-memory store like `crate::Client::new`.
+    /// Use `ClientBuilder::build_blocking` to configure the client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client's runtime can't be started.
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|_| {
+            ClientBuilder::new()
+                .in_memory_tofu_store()
+                .build_blocking()
+                .expect("failed to start the blocking client's runtime")
+        })
+    }
+
+    /// Create a new blocking client with a TOFU store loaded from the default path, failing if the store can't be loaded.
+    pub fn try_new() -> Result<Self, ClientError> {
+        ClientBuilder::new().build_blocking()
+    }
+
+    /// Get the time left before the client sends requests to a host again, if it asked the client to slow down.
+    pub fn cooldown(&self, host: &Host) -> Option<Duration> {
+        self.inner.cooldown(host)
+    }
+
+    /// Remove every response from the client's cache.
+    pub fn clear_cache(&self) {
+        self.inner.clear_cache()
+    }
+
+    /// Set whether the client is offline. See `crate::Client::set_offline`.
+    pub fn set_offline(&self, offline: bool) {
+        self.inner.set_offline(offline)
+    }
+
+    /// Get whether the client is offline.
+    pub fn is_offline(&self) -> bool {
+        self.inner.is_offline()
+    }
+
+    /// Get the statistics for a host. See `crate::Client::host_stats`.
+    pub fn host_stats(&self, host: &Host) -> Option<HostStats> {
+        self.inner.host_stats(host)
+    }
+
+    /// Get the statistics for every host, most visited first.
+    pub fn all_host_stats(&self) -> Vec<(Host, HostStats)> {
+        self.inner.all_host_stats()
+    }
+
+    /// Save the host statistics to their file now. See `crate::Client::save_host_stats`.
+    pub fn save_host_stats(&self) -> Result<(), ClientError> {
+        self.inner.save_host_stats()
+    }
+
+    /// Describe the client's environment in a gemtext document, for including in bug reports.
+    pub fn diagnostics(&self) -> String {
+        self.inner.diagnostics()
+    }
+
+    /// Present an identity for requests to `host` whose path is `path_prefix` or below it. See `crate::Client::add_identity`.
+    pub fn add_identity(&self, host: &Host, path_prefix: &str, identity: Identity) -> Result<(), ClientError> {
+        self.inner.add_identity(host, path_prefix, identity)
+    }
+
+    /// Generate a self-signed identity and present it for `host` and `path_prefix`, returning its fingerprint.
+    #[cfg(feature = "identity-generation")]
+    pub fn generate_identity(&self, host: &Host, path_prefix: &str, common_name: &str, validity: Duration) -> Result<String, ClientError> {
+        self.inner.generate_identity(host, path_prefix, common_name, validity)
+    }
+
+    /// Stop presenting the identity set for `host` and `path_prefix`, returning it.
+    pub fn remove_identity(&self, host: &Host, path_prefix: &str) -> Option<Identity> {
+        self.inner.remove_identity(host, path_prefix)
+    }
+
+    /// Get a snapshot of the client's metrics.
+    pub fn metrics(&self) -> ClientMetrics {
+        self.inner.metrics()
+    }
+
+    /// Get how fast the client has been reading responses across all hosts, in bytes per second, measured over about the last second.
+    pub fn throughput(&self) -> f64 {
+        self.inner.throughput()
+    }
+
+    /// Get the permanent redirects the client remembers. See `crate::Client::permanent_redirects`.
+    pub fn permanent_redirects(&self) -> Vec<(URL, URL)> {
+        self.inner.permanent_redirects()
+    }
+
+    /// Forget the permanent redirect from a URL, so the next request for it goes to the URL itself again.
+    pub fn forget_permanent_redirect(&self, url: &URL) -> Result<(), ClientError> {
+        self.inner.forget_permanent_redirect(url)
+    }
+
+    /// Forget every permanent redirect the client remembers.
+    pub fn clear_permanent_redirects(&self) -> Result<(), ClientError> {
+        self.inner.clear_permanent_redirects()
+    }
+
+    /// Establish a TLS connection with a host. See `crate::Client::establish_tls_connection`.
+    pub fn establish_tls_connection(&self, url: &URL) -> Result<TlsConnection, ClientError> {
+        self.runtime.block_on(self.inner.establish_tls_connection(url))
+    }
+
+    /// Send a request to the host and return the response/error.
+    pub fn send_request(&self, request: Request, tls_connection: &mut TlsConnection) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.send_request(request, tls_connection))
+    }
+
+    /// Fetch a URL, following redirects as allowed by the client's redirect policy, and return the final response.
+    pub fn fetch(&self, url: &URL) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.fetch(url))
+    }
+
+    /// Fetch a URL, also returning the URL the final response came from and the redirects followed to get there.
+    pub fn fetch_with_chain(&self, url: &URL) -> Result<FetchResult, ClientError> {
+        self.runtime.block_on(self.inner.fetch_with_chain(url))
+    }
+
+    /// Fetch a URL with the given priority. See `crate::Client::fetch_with_priority`.
+    pub fn fetch_with_priority(&self, url: &URL, priority: Priority) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.fetch_with_priority(url, priority))
+    }
+
+    /// Fetch several URLs, with at most `concurrency` fetches in flight at once, and return the results in the same order as the URLs.
+    pub fn fetch_all(&self, urls: &[URL], concurrency: usize) -> Vec<Result<Response, ClientError>> {
+        self.runtime.block_on(self.inner.fetch_all(urls, concurrency))
+    }
+
+    /// Fetch a batch job's pending URLs, saving its progress after each fetch. See `crate::Client::run_batch`.
+    pub fn run_batch(
+        &self,
+        job: &mut BatchJob,
+        concurrency: usize,
+        on_result: impl FnMut(&URL, Result<Response, ClientError>) + Send,
+    ) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.run_batch(job, concurrency, on_result))
+    }
+
+    /// Fetch a URL, unless its body still has the hash `previous_hash`. See `crate::Client::fetch_if_changed`.
+    pub fn fetch_if_changed(&self, url: &URL, previous_hash: &str) -> Result<ChangeCheck, ClientError> {
+        self.runtime.block_on(self.inner.fetch_if_changed(url, previous_hash))
+    }
+
+    /// Fetch a URL, answering requests for input with `answer`. See `crate::Client::fetch_with_input`.
+    pub fn fetch_with_input(&self, url: &URL, mut answer: impl FnMut(InputPrompt) -> Option<String>) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.fetch_with_input(url, |prompt| std::future::ready(answer(prompt))))
+    }
+
+    /// Fetch a URL, answering the first request for input with `input`.
+    pub fn fetch_with_answer(&self, url: &URL, input: &str) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.fetch_with_answer(url, input))
+    }
+
+    /// Fetch a URL and stream the body of a successful response to a file. See `crate::Client::download`.
+    pub fn download(&self, url: &URL, path: impl AsRef<Path>, on_progress: impl FnMut(u64)) -> Result<Response, ClientError> {
+        self.runtime.block_on(self.inner.download(url, path, on_progress))
+    }
+
+    /// Diagnose problems reaching a host. See `crate::Client::diagnose`.
+    pub fn diagnose(&self, host: &Host) -> Diagnosis {
+        self.runtime.block_on(self.inner.diagnose(host))
+    }
+
+    /// Check recorded fingerprints against the TOFU store in bulk, returning a status for each in the same order.
+    pub fn verify_fingerprints(&self, records: &[RecordedFingerprint]) -> Vec<FingerprintStatus> {
+        self.inner.verify_fingerprints(records)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,90 @@
-pub mod request;
-pub mod response;
+/// Emit a `tracing` debug event, if the `tracing` feature is enabled.
+macro_rules! debug_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+pub mod about;
+pub mod archive;
+pub mod authority;
+pub mod batch;
+pub mod cache;
+pub mod certificate;
+pub mod change;
+pub mod cooldown;
+pub mod diagnose;
+pub mod download;
+pub mod handle;
+pub mod host_stats;
+pub mod identity;
+pub mod input;
+pub mod locale;
+pub mod metrics;
+pub mod middleware;
+pub mod politeness;
+pub mod redirect;
+pub mod resolve;
+pub mod retry;
+pub mod socks;
+pub mod strict;
+pub mod throttle;
+pub mod tofu_decision;
+mod permanent_redirects;
+mod tls_parameters;
 mod tofu;
 
-use crate::url::URL;
-use request::Request;
-use response::Response;
-use std::sync::Arc;
+use crate::{request::Request, response::{HeaderParseError, Response, MAX_HEADER_LENGTH}, url::{Host, Scheme, URL}};
+use cache::{CachePolicy, ResponseCache};
+use cooldown::{CooldownPolicy, Cooldowns, DEFAULT_MAX_COOLDOWN};
+use host_stats::HostStatsStore;
+use metrics::{ClientMetrics, Metrics, MetricsRecorder};
+use authority::{AuthorityVerifier, CertificateAuthority};
+use identity::{CertificateRejection, CertificateRemediation, CertificateRequest, Identity, IdentityStore};
+use locale::Catalog;
+use middleware::Middleware;
+use resolve::{Resolver, SystemResolver};
+use permanent_redirects::PermanentRedirects;
+use tls_parameters::{TlsParameters, TlsParametersStore};
+use politeness::{PolitenessPolicy, Priority, Scheduler};
+use redirect::{FetchResult, RedirectPolicy};
+use retry::{RetryEvent, RetryPolicy};
+use socks::Socks5Proxy;
+use strict::SpecViolation;
+use throttle::{BandwidthPolicy, Throttle};
+use std::{
+    future::Future,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc, Mutex},
+    task::Poll,
+    time::{Duration, Instant},
+};
 use tofu::{TofuStore, TofuVerifier};
+pub use tofu::FingerprintAlgorithm;
+use tofu_decision::TofuDecisionHandler;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
+    net::{TcpSocket, TcpStream},
+    time::error::Elapsed,
 };
 use tokio_rustls::{client::TlsStream, TlsConnector};
-use rustls::pki_types::ServerName;
+use rustls::{crypto::CryptoProvider, pki_types::ServerName};
+
+const DEFAULT_TOFU_STORE_PATH: &str = "known_hosts.json";
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// The largest response body a client configured with `ClientBuilder::low_memory` accepts.
+const LOW_MEMORY_MAX_BODY_SIZE: usize = 1024 * 1024;
+#[cfg(feature = "identity-generation")]
+const SESSION_IDENTITY_NAME: &str = "yagc session";
+#[cfg(feature = "identity-generation")]
+const SESSION_IDENTITY_VALIDITY: Duration = Duration::from_secs(365 * 24 * 60 * 60);
 
 /// An error that can occur when the client tries to do something.
 #[allow(dead_code)]
@@ -24,8 +96,131 @@ pub enum ClientError {
     FailedToResolveHostAddress(String),
     /// The connection to the host could not be established.
     FailedToConnectToHost(String),
-    /// A response from the host was received but could not be parsed.
+    /// The SOCKS5 proxy could not connect to the host.
+    ProxyConnectionFailed(String),
+    /// A response from the host could not be read.
     FailedToReadResponse(String),
+    /// A response from the host was received but its header could not be parsed.
+    InvalidResponseHeader(HeaderParseError),
+    /// The response header was longer than the client's maximum header length.
+    HeaderTooLong(String),
+    /// The response body was longer than the client's maximum body size.
+    ResponseTooLarge(String),
+    /// A downloaded response could not be written to its file.
+    FailedToWriteFile(String),
+    /// The runtime of a blocking client could not be started.
+    FailedToStartRuntime(String),
+    /// A batch job's saved progress could not be loaded.
+    FailedToLoadBatchJob(String),
+    /// A batch job's progress could not be saved.
+    FailedToSaveBatchJob(String),
+    /// A redirect's target is not a valid URL that can be requested.
+    InvalidRedirectTarget(String),
+    /// The redirect limit of the client's redirect policy was reached.
+    TooManyRedirects(String),
+    /// A redirect led back to a URL that was already visited.
+    RedirectLoop(String),
+    /// A redirect led to a different host, which the client's redirect policy does not allow.
+    CrossHostRedirect(String),
+    /// The TOFU store could not be loaded.
+    FailedToLoadTofuStore(String),
+    /// The stored host statistics could not be loaded.
+    FailedToLoadHostStats(String),
+    /// The host statistics could not be saved.
+    FailedToSaveHostStats(String),
+    /// The remembered permanent redirects could not be loaded.
+    FailedToLoadPermanentRedirects(String),
+    /// The remembered permanent redirects could not be saved.
+    FailedToSavePermanentRedirects(String),
+    /// The remembered TLS parameters could not be loaded.
+    FailedToLoadTlsParameters(String),
+    /// Resolving and connecting to the host took longer than the connect timeout.
+    ConnectTimeout(String),
+    /// The TLS handshake took longer than the handshake timeout.
+    HandshakeTimeout(String),
+    /// The host sent nothing for longer than the read timeout.
+    ReadTimeout(String),
+    /// The whole fetch took longer than the total timeout. The URL is given without its query.
+    RequestTimeout(String),
+    /// The fetch was cancelled. The URL is given without its query.
+    Cancelled(String),
+    /// In strict mode, the response deviated from the spec in these ways.
+    SpecViolations(Vec<SpecViolation>),
+    /// The host asked the client to slow down and its cooldown has not elapsed yet.
+    HostCoolingDown(String),
+    /// The URL's scheme can't be requested without a proxy.
+    UnsupportedScheme(String),
+    /// The client is offline and has no cached response for the URL. The URL is given without its query.
+    OfflineMiss(String),
+    /// The client certificate or its key could not be loaded or used.
+    InvalidIdentity(String),
+    /// A certificate authority's root certificates could not be loaded.
+    InvalidCertificateAuthority(String),
+    /// The request could not be sent in full.
+    RequestWriteFailed {
+        /// The number of bytes of the request that were written before the failure.
+        written: usize,
+        /// Why the write failed.
+        reason: String,
+    },
+}
+
+/// Timeouts for the stages of a request. A timeout of `None` means the stage may take any amount of time.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timeouts {
+    /// The time allowed to resolve the host's address and open a TCP connection to it.
+    pub connect: Option<Duration>,
+    /// The time allowed for the TLS handshake.
+    pub handshake: Option<Duration>,
+    /// The time allowed for each write while sending the request.
+    pub write: Option<Duration>,
+    /// The time allowed to wait for more data from the host while reading the response.
+    pub read: Option<Duration>,
+    /// The time allowed for a whole `Client::fetch`, including redirects.
+    pub total: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(DEFAULT_CONNECT_TIMEOUT),
+            handshake: Some(DEFAULT_HANDSHAKE_TIMEOUT),
+            write: Some(DEFAULT_WRITE_TIMEOUT),
+            read: Some(DEFAULT_READ_TIMEOUT),
+            total: None,
+        }
+    }
+}
+
+/// Run futures concurrently on the current task and collect their outputs in order.
+async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    std::future::poll_fn(|cx| {
+        let mut is_pending = false;
+
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => is_pending = true,
+                }
+            }
+        }
+
+        if is_pending { Poll::Pending } else { Poll::Ready(()) }
+    }).await;
+
+    outputs.into_iter().map(|output| output.unwrap()).collect()
+}
+
+/// Run a future, giving up once `duration` has passed if it is set.
+async fn with_timeout<T>(duration: Option<Duration>, future: impl Future<Output = T>) -> Result<T, Elapsed> {
+    match duration {
+        Some(duration) => tokio::time::timeout(duration, future).await,
+        None => Ok(future.await),
+    }
 }
 
 /// A TLS protocol version.
@@ -39,85 +234,1283 @@ pub enum TlsProtocolVersion {
     Unknown,
 }
 
+impl TlsProtocolVersion {
+    /// Get the protocol version negotiated on a TLS stream.
+    fn of(tls_stream: &TlsStream<TcpStream>) -> Self {
+        tls_stream.get_ref().1.protocol_version()
+            .map(|v| match v {
+                rustls::ProtocolVersion::TLSv1_3 => Self::Tls1_3,
+                rustls::ProtocolVersion::TLSv1_2 => Self::Tls1_2,
+                _ => Self::Unknown,
+            })
+            .unwrap_or(Self::Unknown)
+    }
+}
+
+/// An IP address family.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum AddressFamily {
+    /// IPv4.
+    Ipv4,
+    /// IPv6.
+    Ipv6,
+}
+
+/// When the client closes its side of a connection, by sending a TLS close_notify and shutting down its TCP write side.
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum CloseBehavior {
+    /// Close once the whole response has been read. Servers never see a half-closed connection.
+    #[default]
+    AfterResponse,
+    /// Close right after sending the request, half-closing the connection while the response is read.
+    /// For servers that wait for the client to finish before they respond.
+    AfterRequest,
+    /// Never close, just drop the connection once the response has been read.
+    /// For servers that misbehave when they receive a close_notify.
+    Never,
+}
+
 /// A TLS connection.
 pub struct TlsConnection {
     stream: TlsStream<TcpStream>,
+    host: Host,
     /// The TLS protocol version used by the connected server.
     pub protocol_version: TlsProtocolVersion,
+    /// The address the connection was made to.
+    pub peer_address: SocketAddr,
+    /// How long it took to resolve the host's address.
+    pub resolution_time: Duration,
 }
 
+impl TlsConnection {
+    /// Get the address family of the address the connection was made to.
+    pub fn address_family(&self) -> AddressFamily {
+        match self.peer_address {
+            SocketAddr::V4(_) => AddressFamily::Ipv4,
+            SocketAddr::V6(_) => AddressFamily::Ipv6,
+        }
+    }
+}
+
+/// A function called before each retry.
+type RetryHook = Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+/// A function called when a host asks for a certificate the client didn't present.
+type CertificateHook = Arc<dyn Fn(&CertificateRequest) -> Option<Identity> + Send + Sync>;
+/// A function called when a host rejects the identity the client presented.
+type RejectionHook = Arc<dyn Fn(&CertificateRejection) -> CertificateRemediation + Send + Sync>;
+/// A function that rewrites the URLs sent to a Gemini proxy.
+type ProxyRequestHook = Arc<dyn Fn(&URL) -> URL + Send + Sync>;
+
 /// A client for the Gemini protocol.
+///
+/// `Client` is `Send + Sync`, so one client can be shared between tasks behind an `Arc` and fetch concurrently.
+/// Concurrent fetches share the client's TOFU store, cooldowns and politeness limits.
 pub struct Client {
-    tofu_store: TofuStore,
+    tofu_verifier: Arc<TofuVerifier>,
+    redirect_policy: RedirectPolicy,
+    retry_policy: RetryPolicy,
+    on_retry: Option<RetryHook>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    metrics: Metrics,
+    cooldowns: Cooldowns,
+    cooldown_policy: CooldownPolicy,
+    scheduler: Option<Scheduler>,
+    throttle: Throttle,
+    cache: Option<ResponseCache>,
+    offline: AtomicBool,
+    permanent_redirects: Option<Mutex<PermanentRedirects>>,
+    tls_parameters: Option<Mutex<TlsParametersStore>>,
+    host_stats: Option<Mutex<HostStatsStore>>,
+    require_tls_1_3: bool,
+    strict: bool,
+    timeouts: Timeouts,
+    max_header_length: usize,
+    max_body_size: Option<usize>,
+    close_behavior: CloseBehavior,
+    proxy: Option<Host>,
+    decorate_proxy_requests: Option<ProxyRequestHook>,
+    socks5_proxy: Option<Socks5Proxy>,
+    resolver: Arc<dyn Resolver>,
+    local_address: Option<IpAddr>,
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    interface: Option<String>,
+    identity: Option<Identity>,
+    identities: Mutex<IdentityStore>,
+    on_certificate_required: Option<CertificateHook>,
+    on_certificate_rejected: Option<RejectionHook>,
+    catalog: Option<Arc<dyn Catalog>>,
+    tofu_decision_handler: Option<Arc<dyn TofuDecisionHandler>>,
+}
+
+/// A builder for `Client`s.
+pub struct ClientBuilder {
+    tofu_store_path: Option<String>,
+    auto_reload_tofu_store: bool,
+    redirect_policy: RedirectPolicy,
+    retry_policy: RetryPolicy,
+    on_retry: Option<RetryHook>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    cooldown_policy: CooldownPolicy,
+    max_cooldown: Duration,
+    politeness_policy: Option<PolitenessPolicy>,
+    bandwidth_policy: BandwidthPolicy,
+    cache_policy: Option<CachePolicy>,
+    offline: bool,
+    remember_permanent_redirects: bool,
+    permanent_redirect_store_path: Option<PathBuf>,
+    remember_tls_parameters: bool,
+    tls_parameters_store_path: Option<PathBuf>,
+    record_host_stats: bool,
+    host_stats_store_path: Option<PathBuf>,
+    require_tls_1_3: bool,
+    strict: bool,
+    timeouts: Timeouts,
+    max_header_length: usize,
+    max_body_size: Option<usize>,
+    close_behavior: CloseBehavior,
+    proxy: Option<Host>,
+    decorate_proxy_requests: Option<ProxyRequestHook>,
+    socks5_proxy: Option<Socks5Proxy>,
+    resolver: Arc<dyn Resolver>,
+    local_address: Option<IpAddr>,
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    interface: Option<String>,
+    identity: Option<Result<Identity, String>>,
+    #[cfg(feature = "identity-generation")]
+    session_identity: bool,
+    identity_store: IdentityStore,
+    on_certificate_required: Option<CertificateHook>,
+    on_certificate_rejected: Option<RejectionHook>,
+    catalog: Option<Arc<dyn Catalog>>,
+    tofu_decision_handler: Option<Arc<dyn TofuDecisionHandler>>,
+    certificate_authorities: Vec<CertificateAuthority>,
+    fingerprint_algorithm: FingerprintAlgorithm,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    /// Create a new `ClientBuilder`.
+    pub fn new() -> Self {
+        Self {
+            tofu_store_path: Some(DEFAULT_TOFU_STORE_PATH.to_string()),
+            auto_reload_tofu_store: false,
+            redirect_policy: RedirectPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            on_retry: None,
+            middleware: Vec::new(),
+            metrics_recorder: None,
+            cooldown_policy: CooldownPolicy::default(),
+            max_cooldown: DEFAULT_MAX_COOLDOWN,
+            politeness_policy: None,
+            bandwidth_policy: BandwidthPolicy::default(),
+            cache_policy: None,
+            offline: false,
+            remember_permanent_redirects: false,
+            permanent_redirect_store_path: None,
+            remember_tls_parameters: false,
+            tls_parameters_store_path: None,
+            record_host_stats: false,
+            host_stats_store_path: None,
+            require_tls_1_3: false,
+            strict: false,
+            timeouts: Timeouts::default(),
+            max_header_length: MAX_HEADER_LENGTH,
+            max_body_size: None,
+            close_behavior: CloseBehavior::default(),
+            proxy: None,
+            decorate_proxy_requests: None,
+            socks5_proxy: None,
+            resolver: Arc::new(SystemResolver),
+            local_address: None,
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            interface: None,
+            identity: None,
+            #[cfg(feature = "identity-generation")]
+            session_identity: false,
+            identity_store: IdentityStore::new(),
+            on_certificate_required: None,
+            on_certificate_rejected: None,
+            catalog: None,
+            tofu_decision_handler: None,
+            certificate_authorities: Vec::new(),
+            fingerprint_algorithm: FingerprintAlgorithm::default(),
+        }
+    }
+
+    /// Set the path of the TOFU store. The path must end with `.json`.
+    pub fn tofu_store_path(mut self, path: String) -> Self {
+        self.tofu_store_path = Some(path);
+        self
+    }
+
+    /// Reload the TOFU store from its file before verifying a certificate if another process has changed it,
+    /// so processes sharing the file honor each other's trust decisions without restarting.
+    pub fn auto_reload_tofu_store(mut self) -> Self {
+        self.auto_reload_tofu_store = true;
+        self
+    }
+
+    /// Keep the TOFU store in memory only, so nothing is loaded from or saved to disk.
+    pub fn in_memory_tofu_store(mut self) -> Self {
+        self.tofu_store_path = None;
+        self
+    }
+
+    /// Set the policy used to follow redirects in `Client::fetch`.
+    pub fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.redirect_policy = redirect_policy;
+        self
+    }
+
+    /// Remember the permanent redirects the client follows, and request their targets directly from then on, as the spec suggests.
+    /// The redirects are kept in memory unless a store path is set with `permanent_redirect_store_path`.
+    pub fn remember_permanent_redirects(mut self) -> Self {
+        self.remember_permanent_redirects = true;
+        self
+    }
+
+    /// Remember permanent redirects in the JSON file at `path`, so they are kept between runs.
+    pub fn permanent_redirect_store_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.remember_permanent_redirects = true;
+        self.permanent_redirect_store_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Remember the TLS version and key exchange group each host's handshake settled on, and start later handshakes with them:
+    /// TLS 1.2 isn't offered to hosts that spoke TLS 1.3, and the group the host chose is offered first, saving a round trip.
+    /// A host whose handshake fails with its remembered parameters is forgotten, so the next handshake offers everything again.
+    /// The parameters are kept in memory unless a store path is set with `tls_parameters_store_path`.
+    pub fn remember_tls_parameters(mut self) -> Self {
+        self.remember_tls_parameters = true;
+        self
+    }
+
+    /// Remember TLS parameters in the JSON file at `path`, so they are kept between runs.
+    pub fn tls_parameters_store_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.remember_tls_parameters = true;
+        self.tls_parameters_store_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the policy used to retry failed requests.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set a function to be called before each retry, e.g. for logging.
+    pub fn on_retry(mut self, on_retry: impl Fn(&RetryEvent) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(on_retry));
+        self
+    }
+
+    /// Add middleware to run on each request made by `Client::fetch`, after any middleware already added.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Set a recorder to pass the outcome of each request to, for exporting metrics. `Client::metrics` works without one.
+    pub fn metrics_recorder(mut self, recorder: impl MetricsRecorder + 'static) -> Self {
+        self.metrics_recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Set what happens to requests to a host that asked the client to slow down.
+    pub fn cooldown_policy(mut self, cooldown_policy: CooldownPolicy) -> Self {
+        self.cooldown_policy = cooldown_policy;
+        self
+    }
+
+    /// Set the longest a host can make the client wait by asking it to slow down. Longer cooldowns are cut short to this.
+    /// The default is 5 minutes.
+    pub fn max_cooldown(mut self, max_cooldown: Duration) -> Self {
+        self.max_cooldown = max_cooldown;
+        self
+    }
+
+    /// Limit the rate of requests made by `Client::fetch` with a politeness policy. By default, requests are not limited.
+    pub fn politeness_policy(mut self, politeness_policy: PolitenessPolicy) -> Self {
+        self.politeness_policy = Some(politeness_policy);
+        self
+    }
+
+    /// Limit how fast responses are read. By default, reads are not limited.
+    pub fn bandwidth_policy(mut self, bandwidth_policy: BandwidthPolicy) -> Self {
+        self.bandwidth_policy = bandwidth_policy;
+        self
+    }
+
+    /// Keep statistics about each host the client makes requests to, such as how often it was visited and how fast it responded.
+    /// The statistics are kept in memory unless a store path is set with `host_stats_store_path`.
+    pub fn record_host_stats(mut self) -> Self {
+        self.record_host_stats = true;
+        self
+    }
+
+    /// Keep host statistics in the JSON file at `path`, so they are kept between runs.
+    /// The file is written every 30 seconds while requests are made and when the client is dropped, or with `Client::save_host_stats`.
+    pub fn host_stats_store_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.record_host_stats = true;
+        self.host_stats_store_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Cache successful responses in memory with a cache policy, so fetching the same URL again doesn't hit the network.
+    /// By default, responses are not cached.
+    pub fn cache_policy(mut self, cache_policy: CachePolicy) -> Self {
+        self.cache_policy = Some(cache_policy);
+        self
+    }
+
+    /// Set whether the client starts offline. See `Client::set_offline`.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set whether connections must use TLS 1.3. By default, TLS 1.2 is also accepted.
+    pub fn require_tls_1_3(mut self, require_tls_1_3: bool) -> Self {
+        self.require_tls_1_3 = require_tls_1_3;
+        self
+    }
+
+    /// Set whether responses that deviate from the spec are rejected with `ClientError::SpecViolations`, which lists each deviation.
+    /// Useful for testing servers. By default, deviations the client can work with are tolerated.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the timeouts for the stages of a request.
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Set the maximum length of a response header, not counting the CRLF.
+    /// By default, this is the 1027 bytes the spec allows (a 1024 byte meta); raise it to accept non-conforming servers.
+    pub fn max_header_length(mut self, max_header_length: usize) -> Self {
+        self.max_header_length = max_header_length;
+        self
+    }
+
+    /// Set the maximum size of a response body in bytes. Longer responses are abandoned. By default, bodies may be any size.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Configure the client for devices with little memory, such as routers and handhelds.
+    /// Nothing is kept in memory between requests beyond the TOFU store, cooldowns and identities: there is no response cache,
+    /// and permanent redirects, TLS parameters and host statistics aren't remembered. Response headers are limited to the
+    /// length the spec allows and bodies to 1 MiB. `Client::download` keeps only a chunk of a body in memory at a time,
+    /// so to download bigger files, raise the limit with `max_body_size` after this.
+    pub fn low_memory(mut self) -> Self {
+        self.cache_policy = None;
+        self.remember_permanent_redirects = false;
+        self.permanent_redirect_store_path = None;
+        self.remember_tls_parameters = false;
+        self.tls_parameters_store_path = None;
+        self.record_host_stats = false;
+        self.host_stats_store_path = None;
+        self.max_header_length = MAX_HEADER_LENGTH;
+        self.max_body_size = Some(LOW_MEMORY_MAX_BODY_SIZE);
+        self
+    }
+
+    /// Set when the client closes its side of each connection. By default, it closes once the whole response has been read.
+    pub fn close_behavior(mut self, close_behavior: CloseBehavior) -> Self {
+        self.close_behavior = close_behavior;
+        self
+    }
+
+    /// Send every request to a Gemini proxy instead of the URL's own host.
+    /// The proxy receives the full URL, so it can also serve URLs with other schemes, such as `http` or `gopher`.
+    pub fn proxy(mut self, proxy: Host) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Set a function to rewrite each URL sent to the Gemini proxy, e.g. to add the query parameters some proxies accept for cache control.
+    /// Only the request sent to the proxy is changed: redirects, the cache and the URLs returned use the URL as requested.
+    pub fn decorate_proxy_requests(mut self, decorate_proxy_requests: impl Fn(&URL) -> URL + Send + Sync + 'static) -> Self {
+        self.decorate_proxy_requests = Some(Arc::new(decorate_proxy_requests));
+        self
+    }
+
+    /// Open TCP connections through a SOCKS5 proxy, such as Tor's SOCKS port. The proxy resolves hostnames itself.
+    /// TLS still runs end to end, so the TOFU store sees the host's own certificate.
+    pub fn socks5_proxy(mut self, socks5_proxy: Socks5Proxy) -> Self {
+        self.socks5_proxy = Some(socks5_proxy);
+        self
+    }
+
+    /// Set the resolver used to look up hosts' addresses. By default, the operating system's resolver is used.
+    /// Wrap a resolver in a `CachingResolver` to cache its lookups. Hostnames sent to a SOCKS5 proxy are resolved by the proxy.
+    pub fn resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Bind outgoing TCP connections to a local address, e.g. to choose the network used on a multi-homed host.
+    /// Only hosts' addresses of the same family as the local address can be connected to.
+    pub fn local_address(mut self, local_address: IpAddr) -> Self {
+        self.local_address = Some(local_address);
+        self
+    }
+
+    /// Bind outgoing TCP connections to a network interface, such as `wg0`, so they go through it whatever the routing table says.
+    /// This usually needs the `CAP_NET_RAW` capability.
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    pub fn interface(mut self, interface: &str) -> Self {
+        self.interface = Some(interface.to_string());
+        self
+    }
+
+    /// Present a client certificate to hosts that ask for one, loaded from a PEM certificate chain and private key.
+    /// If they can't be loaded, `build` fails with `ClientError::InvalidIdentity`.
+    pub fn identity(mut self, certificate_pem: impl AsRef<[u8]>, key_pem: impl AsRef<[u8]>) -> Self {
+        self.identity = Some(Identity::from_pem(certificate_pem, key_pem));
+        #[cfg(feature = "identity-generation")]
+        {
+            self.session_identity = false;
+        }
+        self
+    }
+
+    /// Present a client certificate whose PKCS#8 key is password-protected, like `identity`.
+    #[cfg(feature = "encrypted-keys")]
+    pub fn encrypted_identity(mut self, certificate_pem: impl AsRef<[u8]>, key_pem: impl AsRef<[u8]>, passphrase: &str) -> Self {
+        self.identity = Some(Identity::from_encrypted_pem(certificate_pem, key_pem, passphrase));
+        #[cfg(feature = "identity-generation")]
+        {
+            self.session_identity = false;
+        }
+        self
+    }
+
+    /// Present a new self-signed identity, generated for each client built and never saved, to hosts that ask for one.
+    /// This suits capsules that only need to recognize a session. It replaces any identity set with `identity`.
+    #[cfg(feature = "identity-generation")]
+    pub fn session_identity(mut self) -> Self {
+        self.identity = None;
+        self.session_identity = true;
+        self
+    }
+
+    /// Present the identities in a store to the hosts and paths they are scoped to. See `Client::add_identity`.
+    pub fn identity_store(mut self, identity_store: IdentityStore) -> Self {
+        self.identity_store = identity_store;
+        self
+    }
+
+    /// Set a function to be called when a host asks for a certificate the client didn't present, e.g. to let the user pick one.
+    /// If it returns an identity, the client presents it for the URL and everything below it, and requests the URL again once.
+    pub fn on_certificate_required(mut self, on_certificate_required: impl Fn(&CertificateRequest) -> Option<Identity> + Send + Sync + 'static) -> Self {
+        self.on_certificate_required = Some(Arc::new(on_certificate_required));
+        self
+    }
+
+    /// Set a function to be called when a host rejects the identity the client presented (status 61 or 62),
+    /// deciding whether to keep presenting it, stop presenting it, or replace it and request the URL again once.
+    /// Without one, the client keeps presenting it.
+    pub fn on_certificate_rejected(mut self, on_certificate_rejected: impl Fn(&CertificateRejection) -> CertificateRemediation + Send + Sync + 'static) -> Self {
+        self.on_certificate_rejected = Some(Arc::new(on_certificate_rejected));
+        self
+    }
+
+    /// Translate the text the client generates, such as its `about:` pages, with a catalog. By default, it is in English.
+    pub fn catalog(mut self, catalog: impl Catalog + 'static) -> Self {
+        self.catalog = Some(Arc::new(catalog));
+        self
+    }
+
+    /// Trust hosts whose certificates a private certificate authority validates, without TOFU.
+    /// TOFU still applies to hosts outside the authority's domains. Hosts in its domains whose certificates it doesn't validate
+    /// are rejected; an authority without domains falls back to TOFU for them instead.
+    pub fn certificate_authority(mut self, authority: CertificateAuthority) -> Self {
+        self.certificate_authorities.push(authority);
+        self
+    }
+
+    /// Ask a handler what to do when a host's certificate doesn't match the TOFU store, instead of failing the connection.
+    /// If it accepts the certificate, the client connects to the host again.
+    pub fn tofu_decision_handler(mut self, handler: impl TofuDecisionHandler + 'static) -> Self {
+        self.tofu_decision_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Set the algorithm the TOFU store records new hosts' fingerprints with. By default, this is SHA-256.
+    /// Hosts known by another algorithm are still verified with it, and also recorded with this one the next time they match.
+    pub fn fingerprint_algorithm(mut self, fingerprint_algorithm: FingerprintAlgorithm) -> Self {
+        self.fingerprint_algorithm = fingerprint_algorithm;
+        self
+    }
+
+    /// Build the client, loading its TOFU store and any permanent redirects it remembers.
+    pub fn build(&self) -> Result<Client, ClientError> {
+        let mut tofu_store = match &self.tofu_store_path {
+            Some(path) => TofuStore::new(path.clone()).map_err(ClientError::FailedToLoadTofuStore)?,
+            None => TofuStore::in_memory(),
+        };
+        tofu_store.set_fingerprint_algorithm(self.fingerprint_algorithm);
+        tofu_store.set_auto_reload(self.auto_reload_tofu_store);
+        let permanent_redirects = match (self.remember_permanent_redirects, &self.permanent_redirect_store_path) {
+            (true, Some(path)) => Some(PermanentRedirects::new(path.clone()).map_err(ClientError::FailedToLoadPermanentRedirects)?),
+            (true, None) => Some(PermanentRedirects::in_memory()),
+            (false, _) => None,
+        };
+        let tls_parameters = match (self.remember_tls_parameters, &self.tls_parameters_store_path) {
+            (true, Some(path)) => Some(TlsParametersStore::new(path.clone()).map_err(ClientError::FailedToLoadTlsParameters)?),
+            (true, None) => Some(TlsParametersStore::in_memory()),
+            (false, _) => None,
+        };
+        let host_stats = match (self.record_host_stats, &self.host_stats_store_path) {
+            (true, Some(path)) => Some(HostStatsStore::new(path.clone()).map_err(ClientError::FailedToLoadHostStats)?),
+            (true, None) => Some(HostStatsStore::in_memory()),
+            (false, _) => None,
+        };
+        let identity = self.identity.clone().transpose().map_err(ClientError::InvalidIdentity)?;
+        #[cfg(feature = "identity-generation")]
+        let identity = match self.session_identity {
+            true => Some(Identity::generate(SESSION_IDENTITY_NAME, SESSION_IDENTITY_VALIDITY).map_err(ClientError::InvalidIdentity)?),
+            false => identity,
+        };
+        let authorities = self.certificate_authorities.iter()
+            .map(|authority| AuthorityVerifier::new(authority.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ClientError::InvalidCertificateAuthority)?;
+
+        let client = Client {
+            tofu_verifier: Arc::new(TofuVerifier::new(tofu_store, authorities)),
+            redirect_policy: self.redirect_policy.clone(),
+            retry_policy: self.retry_policy.clone(),
+            on_retry: self.on_retry.clone(),
+            middleware: self.middleware.clone(),
+            metrics: Metrics::new(self.metrics_recorder.clone()),
+            cooldowns: Cooldowns::new(self.max_cooldown),
+            cooldown_policy: self.cooldown_policy,
+            scheduler: self.politeness_policy.as_ref().map(Scheduler::new),
+            throttle: Throttle::new(&self.bandwidth_policy),
+            cache: self.cache_policy.as_ref().map(ResponseCache::new),
+            offline: AtomicBool::new(self.offline),
+            permanent_redirects: permanent_redirects.map(Mutex::new),
+            tls_parameters: tls_parameters.map(Mutex::new),
+            host_stats: host_stats.map(Mutex::new),
+            require_tls_1_3: self.require_tls_1_3,
+            strict: self.strict,
+            timeouts: self.timeouts.clone(),
+            max_header_length: self.max_header_length,
+            max_body_size: self.max_body_size,
+            close_behavior: self.close_behavior,
+            proxy: self.proxy.clone(),
+            decorate_proxy_requests: self.decorate_proxy_requests.clone(),
+            socks5_proxy: self.socks5_proxy.clone(),
+            resolver: self.resolver.clone(),
+            local_address: self.local_address,
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            interface: self.interface.clone(),
+            identity,
+            identities: Mutex::new(self.identity_store.clone()),
+            on_certificate_required: self.on_certificate_required.clone(),
+            on_certificate_rejected: self.on_certificate_rejected.clone(),
+            catalog: self.catalog.clone(),
+            tofu_decision_handler: self.tofu_decision_handler.clone(),
+        };
+
+        // check the key can be used now rather than on the first connection
+        if let Some(identity) = &client.identity {
+            client.tls_connector(Some(identity), None)?;
+        }
+
+        Ok(client)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Client {
     /// Create a new client with a TOFU store loaded from the default path.
+    /// If the store can't be loaded, the client falls back to an empty in-memory store, forgetting the hosts it knew;
+    /// use `try_new` to handle the error instead. Use `ClientBuilder` to configure the client.
     pub fn new() -> Self {
-        Self { tofu_store: TofuStore::new("known_hosts.json".to_string()).unwrap() }
+        Self::try_new().unwrap_or_else(|_| {
+            // an in-memory store can't fail to load
+            ClientBuilder::new().in_memory_tofu_store().build().unwrap()
+        })
+    }
+
+    /// Create a new client with a TOFU store loaded from the default path, failing if the store can't be loaded.
+    pub fn try_new() -> Result<Self, ClientError> {
+        ClientBuilder::new().build()
+    }
+
+    /// Get the time left before the client sends requests to a host again, if it asked the client to slow down.
+    pub fn cooldown(&self, host: &Host) -> Option<Duration> {
+        self.cooldowns.remaining(host)
+    }
+
+    /// Remove every response from the client's cache.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Set whether the client is offline. While offline, fetches never use the network:
+    /// they are answered from the cache, even with expired responses, and fail with `ClientError::OfflineMiss` otherwise.
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
     }
 
-    /// Establish a TLS connection with a host.
+    /// Get whether the client is offline.
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    /// Get a snapshot of the client's metrics.
+    pub fn metrics(&self) -> ClientMetrics {
+        ClientMetrics { throughput: self.throughput(), ..self.metrics.snapshot() }
+    }
+
+    /// Get how fast the client has been reading responses across all hosts, in bytes per second, measured over about the last second.
+    pub fn throughput(&self) -> f64 {
+        self.throttle.bytes_per_second()
+    }
+
+    /// Establish a TLS connection with a host, or with the client's proxy if it has one.
+    /// If the host asked the client to slow down, this waits for or rejects the connection according to the client's cooldown policy.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(url = %url.without_query())))]
     pub async fn establish_tls_connection(&self, url: &URL) -> Result<TlsConnection, ClientError> {
-        // get the hostname and port from the url
-        let (hostname, port) = if let Some(host) = &url.host {
-            (host.name.clone(), host.port)
-        } else {
-            return Err(ClientError::FailedToResolveHostAddress("URL must contain a host".to_string()));
+        // get the hostname and port to connect to
+        let (hostname, port) = match (&self.proxy, &url.host) {
+            (_, None) => return Err(ClientError::FailedToResolveHostAddress("URL must contain a host".to_string())),
+            (Some(proxy), Some(_)) => (proxy.name.clone(), proxy.port),
+            (None, Some(_)) if url.scheme != Scheme::Gemini => {
+                return Err(ClientError::UnsupportedScheme(format!("{} URLs can only be requested through a proxy", url.scheme)));
+            },
+            (None, Some(host)) => (host.name.clone(), host.port),
         };
 
-        // create a new tofu verifier
-        let config = rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(Arc::new(TofuVerifier::new(self.tofu_store.clone())))
-            .with_no_client_auth();
+        // respect the host's cooldown
+        if let Some(remaining) = url.host.as_ref().and_then(|host| self.cooldowns.remaining(host)) {
+            match self.cooldown_policy {
+                CooldownPolicy::Wait => tokio::time::sleep(remaining).await,
+                CooldownPolicy::Reject => {
+                    return Err(ClientError::HostCoolingDown(format!("{hostname} asked to wait another {}s", remaining.as_secs())));
+                },
+            }
+        }
 
-        let connector = TlsConnector::from(Arc::new(config));
+        let tls_host = Host { name: hostname.clone(), port };
+        let tls_parameters = self.tls_parameters(&tls_host);
+        let connector = self.tls_connector(self.identity_for(url).as_ref(), tls_parameters)?;
 
-        // connect to the host
-        let tcp_stream = TcpStream::connect((hostname.clone(), port))
+        let (tls_stream, peer_address, resolution_time) = match self.handshake(&connector, &tls_host, tls_parameters).await {
+            Ok(handshake) => handshake,
+            // a certificate that failed TOFU gets another handshake if the decision handler trusts it
+            Err(e) => match self.decide_tofu_mismatch(&hostname).await? {
+                true => self.handshake(&connector, &tls_host, tls_parameters).await?,
+                false => return Err(e),
+            },
+        };
+
+        let protocol_version = TlsProtocolVersion::of(&tls_stream);
+        debug_event!(?protocol_version, "completed TLS handshake");
+
+        Ok(TlsConnection {
+            stream: tls_stream,
+            host: tls_host,
+            protocol_version,
+            peer_address,
+            resolution_time,
+        })
+    }
+
+    /// Open a TCP connection to a host and complete a TLS handshake with it, remembering the TLS parameters it settled on.
+    async fn handshake(
+        &self,
+        connector: &TlsConnector,
+        host: &Host,
+        tls_parameters: Option<TlsParameters>,
+    ) -> Result<(TlsStream<TcpStream>, SocketAddr, Duration), ClientError> {
+        let (tcp_stream, peer_address, resolution_time) = with_timeout(self.timeouts.connect, self.open_tcp(&host.name, host.port))
             .await
-            .map_err(|e| ClientError::FailedToConnectToHost(e.to_string()))?;
+            .map_err(|_| ClientError::ConnectTimeout(host.name.clone()))??;
 
         // server name indication
-        let domain = ServerName::try_from(hostname)
+        let domain = ServerName::try_from(host.name.clone())
             .map_err(|e| ClientError::FailedToConnectToHost(e.to_string()))?;
 
         // establish the tls connection
-        let tls_stream = connector.connect(domain, tcp_stream)
-            .await
+        let handshake = with_timeout(self.timeouts.handshake, connector.connect(domain, tcp_stream)).await;
+        if tls_parameters.is_some() && !matches!(handshake, Ok(Ok(_))) {
+            // the host may have changed its TLS configuration, so the next handshake offers everything again
+            self.forget_tls_parameters(host);
+        }
+        let tls_stream = handshake
+            .map_err(|_| ClientError::HandshakeTimeout(host.name.clone()))?
             .map_err(|e| ClientError::FailedToConnectToHost(e.to_string()))?;
+        self.remember_tls_parameters(host, tls_stream.get_ref().1);
 
-        // Get the protocol version
-        let protocol_version = tls_stream.get_ref().1.protocol_version()
-            .map(|v| match v {
-                rustls::ProtocolVersion::TLSv1_3 => TlsProtocolVersion::Tls1_3,
-                rustls::ProtocolVersion::TLSv1_2 => TlsProtocolVersion::Tls1_2,
-                _ => TlsProtocolVersion::Unknown,
-            })
-            .unwrap_or_else(|| TlsProtocolVersion::Unknown);
+        Ok((tls_stream, peer_address, resolution_time))
+    }
+
+    /// Create a TLS connector that verifies certificates with the client's TOFU store and presents `identity`, if any.
+    /// The verifier is shared by all connections, so a host learned by one handshake is known to every later one.
+    /// Each connector has its own session cache, so sessions are never resumed, and a connection presenting one identity
+    /// can't be linked through resumption to one presenting another, or none.
+    /// `tls_parameters` are the parameters a previous handshake with the host settled on, if the client remembers them.
+    fn tls_connector(&self, identity: Option<&Identity>, tls_parameters: Option<TlsParameters>) -> Result<TlsConnector, ClientError> {
+        let protocol_versions: &[&rustls::SupportedProtocolVersion] = if self.require_tls_1_3 || tls_parameters.is_some_and(|parameters| parameters.tls_1_3()) {
+            &[&rustls::version::TLS13]
+        } else {
+            rustls::DEFAULT_VERSIONS
+        };
+        let builder = match tls_parameters {
+            Some(parameters) => {
+                let provider = CryptoProvider::get_default().map_or_else(rustls::crypto::aws_lc_rs::default_provider, |provider| (**provider).clone());
+                rustls::ClientConfig::builder_with_provider(Arc::new(parameters.prefer_key_exchange_group(provider)))
+                    .with_protocol_versions(protocol_versions)
+                    .map_err(|e| ClientError::FailedToConnectToHost(e.to_string()))?
+            },
+            None => rustls::ClientConfig::builder_with_protocol_versions(protocol_versions),
+        };
+        let builder = builder
+            .dangerous()
+            .with_custom_certificate_verifier(self.tofu_verifier.clone());
+        let config = match identity {
+            Some(identity) => {
+                let (certificates, key) = identity.to_parts();
+                builder.with_client_auth_cert(certificates, key).map_err(|e| ClientError::InvalidIdentity(e.to_string()))?
+            },
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    /// Open a TCP connection to the host, through the client's SOCKS5 proxy if it has one.
+    /// With a proxy, the address and resolution time returned are the proxy's.
+    async fn open_tcp(&self, hostname: &str, port: u16) -> Result<(TcpStream, SocketAddr, Duration), ClientError> {
+        let Some(proxy) = &self.socks5_proxy else {
+            return self.connect_tcp(hostname, port).await;
+        };
+
+        let (mut tcp_stream, proxy_address, resolution_time) = self.connect_tcp(&proxy.host.name, proxy.host.port).await?;
+        proxy.connect(&mut tcp_stream, hostname, port)
+            .await
+            .map_err(ClientError::ProxyConnectionFailed)?;
+        debug_event!(%proxy_address, "connected through SOCKS5 proxy");
 
-        Ok(TlsConnection { stream: tls_stream, protocol_version })
+        Ok((tcp_stream, proxy_address, resolution_time))
+    }
+
+    /// Resolve the host's addresses and open a TCP connection to the first one that accepts it.
+    async fn connect_tcp(&self, hostname: &str, port: u16) -> Result<(TcpStream, SocketAddr, Duration), ClientError> {
+        let resolution_start = Instant::now();
+        let addresses = self.resolve(hostname, port)
+            .await
+            .map_err(ClientError::FailedToResolveHostAddress)?;
+        let resolution_time = resolution_start.elapsed();
+        debug_event!(?addresses, ?resolution_time, "resolved host");
+
+        let mut last_error = format!("No addresses found for {hostname}");
+        for address in addresses {
+            match self.connect_socket(address).await {
+                Ok(tcp_stream) => {
+                    debug_event!(%address, "connected");
+                    return Ok((tcp_stream, address, resolution_time));
+                },
+                Err(e) => {
+                    debug_event!(%address, error = %e, "failed to connect");
+                    last_error = e.to_string();
+                },
+            }
+        }
+
+        Err(ClientError::FailedToConnectToHost(last_error))
+    }
+
+    /// Open a TCP connection to an address, from the client's local address and interface if it has them.
+    async fn connect_socket(&self, address: SocketAddr) -> std::io::Result<TcpStream> {
+        let socket = match address {
+            SocketAddr::V4(_) => TcpSocket::new_v4()?,
+            SocketAddr::V6(_) => TcpSocket::new_v6()?,
+        };
+        if let Some(local_address) = self.local_address {
+            socket.bind(SocketAddr::new(local_address, 0))?;
+        }
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        if let Some(interface) = &self.interface {
+            socket.bind_device(Some(interface.as_bytes()))?;
+        }
+
+        socket.connect(address).await
     }
 
     /// Send a request to the host and return the response/error.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(url = %request.0.without_query())))]
     pub async fn send_request(&self, request: Request, tls_connection: &mut TlsConnection) -> Result<Response, ClientError> {
+        self.write_request(&request, tls_connection).await?;
+
+        let mut buffer = Vec::new();
+        if let Some(body_start) = self.read_header(tls_connection, &mut buffer).await? {
+            loop {
+                if let Some(max_body_size) = self.max_body_size
+                    && buffer.len() - body_start > max_body_size
+                {
+                    return Err(self.abandon_response(tls_connection, max_body_size).await);
+                }
+
+                if self.read_more(tls_connection, &mut buffer).await? == 0 {
+                    break;
+                }
+            }
+        }
+
+        debug_event!(bytes = buffer.len(), "read response");
+        if self.close_behavior == CloseBehavior::AfterResponse {
+            self.close(tls_connection).await;
+        }
+
+        let response = Response::from_bytes_with_limit(&buffer, self.max_header_length).map_err(ClientError::InvalidResponseHeader)?;
+        debug_event!(status = response.status_code(), "parsed response header");
+        self.check_spec(&request.0, &buffer, &response)?;
+        self.record_cooldown(&request.0, &response);
+
+        Ok(response)
+    }
+
+    /// Read more of the response into the buffer, returning the number of bytes read.
+    /// Waits before returning if the client's bandwidth policy requires it.
+    async fn read_more(&self, tls_connection: &mut TlsConnection, buffer: &mut Vec<u8>) -> Result<usize, ClientError> {
+        let bytes_read = with_timeout(self.timeouts.read, tls_connection.stream.read_buf(buffer))
+            .await
+            .map_err(|_| ClientError::ReadTimeout(format!("Received {} bytes before timing out", buffer.len())))?
+            .map_err(|_| ClientError::FailedToReadResponse("Failed to read response".to_string()))?;
+
+        self.record_bytes_received(&tls_connection.host, bytes_read);
+        self.throttle.consume(&tls_connection.host, bytes_read).await;
+
+        Ok(bytes_read)
+    }
+
+    /// Read until the end of the response header, returning where the body starts, or `None` if the host closed the connection first.
+    async fn read_header(&self, tls_connection: &mut TlsConnection, buffer: &mut Vec<u8>) -> Result<Option<usize>, ClientError> {
+        loop {
+            // give up as soon as the CRLF can no longer be within the limit
+            let limit = buffer.len().min(self.max_header_length + 2);
+            if let Some(crlf_position) = buffer[..limit].windows(2).position(|window| window == b"\r\n") {
+                return Ok(Some(crlf_position + 2));
+            }
+            if buffer.len() >= self.max_header_length + 2 {
+                return Err(ClientError::HeaderTooLong(format!("No CRLF in the first {} bytes", self.max_header_length + 2)));
+            }
+
+            if self.read_more(tls_connection, buffer).await? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Send close_notify and shut down the write side of the connection, ignoring failures since the response doesn't depend on them.
+    async fn close(&self, tls_connection: &mut TlsConnection) {
+        let _ = with_timeout(self.timeouts.write, tls_connection.stream.shutdown()).await;
+        debug_event!("closed connection");
+    }
+
+    /// Stop reading a response whose body is larger than the maximum body size.
+    async fn abandon_response(&self, tls_connection: &mut TlsConnection, max_body_size: usize) -> ClientError {
+        // stop the host from sending the rest
+        let _ = with_timeout(self.timeouts.read, tls_connection.stream.shutdown()).await;
+
+        ClientError::ResponseTooLarge(format!("Body is longer than {max_body_size} bytes"))
+    }
+
+    /// Start a cooldown for the URL's host if the response asks the client to slow down.
+    fn record_cooldown(&self, url: &URL, response: &Response) {
+        if let (Response::SlowDown { information }, Some(host)) = (response, &url.host) {
+            self.cooldowns.record(host, information);
+        }
+    }
+
+    /// Get the request to send for `request`, decorated for the client's Gemini proxy if it has one and a function to decorate them.
+    fn decorate_for_proxy(&self, request: &Request) -> Request {
+        match (&self.proxy, &self.decorate_proxy_requests) {
+            (Some(_), Some(decorate)) => Request(decorate(&request.0)),
+            _ => Request(request.0.clone()),
+        }
+    }
+
+    /// Write the request to the connection, keeping track of how much of it was sent.
+    async fn write_request(&self, request: &Request, tls_connection: &mut TlsConnection) -> Result<(), ClientError> {
+        let request = self.decorate_for_proxy(request);
         if !request.is_valid_length() {
-            let length = request.0.to_string().as_bytes().len();
+            let length = request.0.to_string().len();
             return Err(ClientError::RequestTooLong(format!("Request is too long: {length} bytes")));
         }
 
-        if let Err(_) = tls_connection.stream.write_all(request.to_string().as_bytes()).await {
-            return Err(ClientError::FailedToConnectToHost(request.0.host.as_ref().unwrap().name.clone()));
+        let request = request.to_string().into_bytes();
+        let mut written = 0;
+
+        while written < request.len() {
+            let result = with_timeout(self.timeouts.write, tls_connection.stream.write(&request[written..]))
+                .await
+                .map_err(|_| ClientError::RequestWriteFailed { written, reason: "Timed out".to_string() })?;
+
+            match result {
+                Ok(0) => return Err(ClientError::RequestWriteFailed { written, reason: "Connection closed".to_string() }),
+                Ok(bytes_written) => {
+                    written += bytes_written;
+                    self.metrics.record_bytes_sent(&tls_connection.host, bytes_written);
+                },
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => continue,
+                Err(e) => return Err(ClientError::RequestWriteFailed { written, reason: e.to_string() }),
+            }
         }
 
-        let mut buffer = Vec::new();
-        tls_connection.stream.read_to_end(&mut buffer)
+        with_timeout(self.timeouts.write, tls_connection.stream.flush())
             .await
-            .map_err(|_| ClientError::FailedToReadResponse("Failed to read response".to_string()))?;
+            .map_err(|_| ClientError::RequestWriteFailed { written, reason: "Timed out while flushing".to_string() })?
+            .map_err(|e| ClientError::RequestWriteFailed { written, reason: e.to_string() })?;
+        debug_event!(bytes = written, "wrote request");
+        if self.close_behavior == CloseBehavior::AfterRequest {
+            self.close(tls_connection).await;
+        }
+
+        Ok(())
+    }
+
+    /// Fetch a URL, following redirects as allowed by the client's redirect policy, and return the final response.
+    pub async fn fetch(&self, url: &URL) -> Result<Response, ClientError> {
+        self.fetch_with_cancel(url, std::future::pending()).await
+    }
+
+    /// Fetch a URL like `fetch`, with the given priority if the politeness policy's concurrency limit is reached.
+    /// Use `Priority::scope` to give a priority to other requests, such as those made by `fetch_all`.
+    pub async fn fetch_with_priority(&self, url: &URL, priority: Priority) -> Result<Response, ClientError> {
+        priority.scope(self.fetch(url)).await
+    }
+
+    /// Fetch several URLs, with at most `concurrency` fetches in flight at once, and return the results in the same order as the URLs.
+    /// Use `spawn_all` instead to await, cancel or follow the progress of each fetch on its own.
+    pub async fn fetch_all(&self, urls: &[URL], concurrency: usize) -> Vec<Result<Response, ClientError>> {
+        let next_index = AtomicUsize::new(0);
+
+        // each worker takes the next unfetched URL until there are none left
+        let worker = || async {
+            let mut results = Vec::new();
+
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(url) = urls.get(index) else {
+                    return results;
+                };
+
+                results.push((index, self.fetch(url).await));
+            }
+        };
+
+        let mut results: Vec<(usize, Result<Response, ClientError>)> = join_all((0..concurrency.max(1)).map(|_| worker()))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        results.sort_by_key(|(index, _)| *index);
+
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Fetch a URL like `fetch`, but give up with `ClientError::Cancelled` as soon as `cancel` completes.
+    /// If a request is in flight at that point, its TLS connection is shut down cleanly.
+    pub async fn fetch_with_cancel(&self, url: &URL, cancel: impl Future<Output = ()>) -> Result<Response, ClientError> {
+        self.fetch_chain(url, false, cancel)
+            .await
+            .map(|result| result.response)
+    }
+
+    /// Fetch a URL like `fetch`, but also return the URL the final response came from and the redirects followed to get there.
+    pub async fn fetch_with_chain(&self, url: &URL) -> Result<FetchResult, ClientError> {
+        self.fetch_chain(url, false, std::future::pending()).await
+    }
+
+    /// Fetch a URL under the total timeout, returning the final response and the redirects followed.
+    /// `sensitive` marks requests carrying sensitive input, which are only retried if the retry policy allows it.
+    async fn fetch_chain(&self, url: &URL, sensitive: bool, cancel: impl Future<Output = ()>) -> Result<FetchResult, ClientError> {
+        let mut cancel = std::pin::pin!(cancel);
+
+        with_timeout(self.timeouts.total, self.follow_redirects(url, sensitive, &mut cancel))
+            .await
+            .map_err(|_| ClientError::RequestTimeout(url.without_query().to_string()))?
+    }
+
+    async fn follow_redirects(&self, url: &URL, sensitive: bool, cancel: &mut (impl Future<Output = ()> + Unpin)) -> Result<FetchResult, ClientError> {
+        let mut visited = vec![url.clone()];
+
+        loop {
+            for target in self.remembered_redirects(visited.last().unwrap()) {
+                if let Err(e) = self.redirect_policy.check(&target, &visited) {
+                    return Err(self.report_error(visited.last().unwrap(), e));
+                }
+
+                self.before_redirect(visited.last().unwrap(), &target);
+                visited.push(target);
+            }
+            let url = visited.last_mut().unwrap();
+
+            let result = match self.before_request(url) {
+                Some(response) => Ok(response),
+                None => self.fetch_cached(url, sensitive, cancel).await,
+            };
+            let response = self.after_request(url, result)?;
+
+            match self.redirect_target(&response, &visited) {
+                Ok(Some(target)) => {
+                    let from = visited.last().unwrap();
+                    if let Err(e) = self.remember_redirect(&response, from, &target, sensitive) {
+                        return Err(self.report_error(from, e));
+                    }
+
+                    self.before_redirect(from, &target);
+                    visited.push(target);
+                },
+                Ok(None) => {
+                    let url = visited.pop().unwrap();
+                    return Ok(FetchResult { response, url, chain: visited });
+                },
+                Err(e) => return Err(self.report_error(visited.last().unwrap(), e)),
+            }
+        }
+    }
+
+    /// Get the URL a response redirects to, if it is a redirect the client's redirect policy allows after visiting `visited`.
+    /// Relative targets are resolved against the last URL visited.
+    fn redirect_target(&self, response: &Response, visited: &[URL]) -> Result<Option<URL>, ClientError> {
+        let target = match response {
+            Response::TemporaryRedirect { url } | Response::PermanentRedirect { url } => url,
+            _ => return Ok(None),
+        };
+        if target.is_empty() {
+            return Err(ClientError::InvalidRedirectTarget("The redirect has no target".to_string()));
+        }
+
+        // visited always starts with the URL that was requested
+        let target = visited.last().unwrap()
+            .join(target)
+            .map_err(|e| ClientError::InvalidRedirectTarget(format!("{target}: {e}")))?;
+        if target.host.is_none() {
+            return Err(ClientError::InvalidRedirectTarget(format!("{target}: The URL has no host")));
+        }
+
+        self.redirect_policy.check(&target, visited)?;
+
+        Ok(Some(target))
+    }
+
+    /// Get a URL's response from the client's cache, or request it and cache the response.
+    /// Responses to requests carrying sensitive input are never cached, and responses to requests presenting an identity
+    /// are only served to requests presenting the same one.
+    async fn fetch_cached(&self, url: &URL, sensitive: bool, cancel: &mut (impl Future<Output = ()> + Unpin)) -> Result<Response, ClientError> {
+        if url.scheme == Scheme::About {
+            return Ok(self.about_page(url));
+        }
+
+        let cache = self.cache.as_ref().filter(|_| !sensitive);
+        let identity = self.identity_for(url).map(|identity| identity.fingerprint());
+
+        if self.is_offline() {
+            return cache
+                .and_then(|cache| cache.get_stale(url, identity.as_deref()))
+                .ok_or_else(|| ClientError::OfflineMiss(url.without_query().to_string()));
+        }
+
+        if let Some(response) = cache.and_then(|cache| cache.get(url, identity.as_deref())) {
+            debug_event!("served from cache");
+            return Ok(response);
+        }
+
+        let response = self.fetch_with_identity(url, sensitive, cancel).await?;
+        if let Some(cache) = cache {
+            // the identity may have changed if the host asked for a certificate
+            let identity = self.identity_for(url).map(|identity| identity.fingerprint());
+            cache.insert(url, identity.as_deref(), &response);
+        }
+
+        Ok(response)
+    }
+
+    /// Request a URL, retrying as allowed by the client's retry policy.
+    async fn fetch_with_retries(&self, url: &URL, sensitive: bool, cancel: &mut (impl Future<Output = ()> + Unpin)) -> Result<Response, ClientError> {
+        let max_retries = if sensitive && !self.retry_policy.retry_sensitive_input {
+            0
+        } else {
+            self.retry_policy.max_retries
+        };
+        let mut retry = 0;
+
+        loop {
+            let start = Instant::now();
+            let result = self.fetch_once(url, cancel).await;
+            self.record_request(url, &result, start.elapsed());
+
+            if retry >= max_retries || !self.retry_policy.should_retry(&result) {
+                return result;
+            }
+
+            retry += 1;
+            let delay = self.retry_policy.backoff(retry);
+            if let Some(on_retry) = &self.on_retry {
+                on_retry(&RetryEvent { url, retry, delay, cause: &result });
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => (),
+                _ = &mut *cancel => return Err(ClientError::Cancelled(url.without_query().to_string())),
+            }
+        }
+    }
+
+    /// Open a connection to the URL's host and send a single request.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(url = %url.without_query())))]
+    async fn fetch_once(&self, url: &URL, cancel: &mut (impl Future<Output = ()> + Unpin)) -> Result<Response, ClientError> {
+        // held until the request is done, so it counts towards the concurrency limit
+        let _permit = match (&self.scheduler, &url.host) {
+            (Some(scheduler), Some(host)) => tokio::select! {
+                permit = scheduler.acquire(host) => Some(permit),
+                _ = &mut *cancel => return Err(ClientError::Cancelled(url.without_query().to_string())),
+            },
+            _ => None,
+        };
+
+        let mut connection = tokio::select! {
+            connection = self.establish_tls_connection(url) => connection?,
+            _ = &mut *cancel => return Err(ClientError::Cancelled(url.without_query().to_string())),
+        };
+
+        let response = tokio::select! {
+            response = self.send_request(Request(url.clone()), &mut connection) => Some(response),
+            _ = &mut *cancel => None,
+        };
+
+        match response {
+            Some(response) => response,
+            None => {
+                // send close_notify rather than just dropping the connection
+                let _ = with_timeout(self.timeouts.read, connection.stream.shutdown()).await;
+
+                Err(ClientError::Cancelled(url.without_query().to_string()))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn client_is_shareable() {
+        assert_send_sync::<Client>();
+
+        // checked at compile time, never run
+        let _ = |client: &Client, url: &URL| {
+            assert_send(&client.fetch(url));
+            assert_send(&client.fetch_all(std::slice::from_ref(url), 2));
+        };
+    }
+
+    #[tokio::test]
+    async fn offline_client_never_uses_the_network() {
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .cache_policy(CachePolicy::default())
+            .offline(true)
+            .build()
+            .unwrap();
+
+        // example.invalid can't resolve, so anything but a miss means the network was used
+        let result = client.fetch(&URL::try_from("gemini://example.invalid/").unwrap()).await;
+        assert!(matches!(result, Err(ClientError::OfflineMiss(_))));
+    }
+
+    #[tokio::test]
+    async fn errors_leave_out_the_query() {
+        let client = ClientBuilder::new().in_memory_tofu_store().offline(true).build().unwrap();
+        let url = URL::try_from("gemini://example.invalid/login").unwrap().with_query("hunter2");
+
+        let result = client.fetch(&url).await;
+        assert!(matches!(result, Err(ClientError::OfflineMiss(url)) if url == "gemini://example.invalid:1965/login"));
+
+        let client = ClientBuilder::new().in_memory_tofu_store().build().unwrap();
+        let result = client.fetch_with_cancel(&url, std::future::ready(())).await;
+        assert!(matches!(result, Err(ClientError::Cancelled(url)) if url == "gemini://example.invalid:1965/login"));
+    }
+
+    #[cfg(feature = "identity-generation")]
+    #[test]
+    fn each_client_gets_its_own_session_identity() {
+        let builder = ClientBuilder::new().in_memory_tofu_store().session_identity();
+        let first = builder.build().unwrap().identity.unwrap();
+        let second = builder.build().unwrap().identity.unwrap();
+
+        assert_ne!(first.fingerprint(), second.fingerprint());
+    }
+
+    #[test]
+    fn requests_to_a_proxy_are_decorated() {
+        let url = URL::try_from("gemini://example.com/page").unwrap();
+        let builder = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .decorate_proxy_requests(|url| url.with_query("no-cache"));
+
+        let client = builder.build().unwrap();
+        assert_eq!(client.decorate_for_proxy(&Request(url.clone())).0, url);
+
+        let client = builder.proxy(Host::try_from("proxy.test").unwrap()).build().unwrap();
+        assert_eq!(client.decorate_for_proxy(&Request(url.clone())).to_string(), "gemini://example.com:1965/page?no-cache\r\n");
+    }
+
+    #[tokio::test]
+    async fn low_memory_clients_keep_nothing_and_cap_bodies() {
+        use identity::tests::{capsule, serve};
+
+        let body = "a".repeat(LOW_MEMORY_MAX_BODY_SIZE + 1);
+        let big = format!("20 text/plain\r\n{body}");
+        let (port, _) = serve(&["20 text/plain\r\nsmall", &big]).await;
+        let client = capsule()
+            .cache_policy(CachePolicy::default())
+            .remember_permanent_redirects()
+            .record_host_stats()
+            .low_memory()
+            .build()
+            .unwrap();
+        assert!(client.cache.is_none() && client.permanent_redirects.is_none() && client.tls_parameters.is_none() && client.host_stats.is_none());
+
+        let url = URL::try_from(format!("gemini://capsule.test:{port}/").as_str()).unwrap();
+        assert!(client.fetch(&url).await.is_ok());
+        assert!(matches!(client.fetch(&url).await, Err(ClientError::ResponseTooLarge(_))));
+    }
 
-        let response_string = String::from_utf8_lossy(&buffer);
+    #[tokio::test]
+    async fn other_schemes_need_a_proxy() {
+        let client = ClientBuilder::new().in_memory_tofu_store().build().unwrap();
+        let result = client.fetch(&URL::try_from("gopher://example.invalid/").unwrap()).await;
 
-        Response::try_from(response_string.as_ref()).map_err(|e| ClientError::FailedToReadResponse(e))
+        assert!(matches!(result, Err(ClientError::UnsupportedScheme(_))));
     }
 }
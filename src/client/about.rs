@@ -0,0 +1,88 @@
+use super::{locale::Message, Client};
+use crate::{
+    response::{MimeType, Response},
+    url::URL,
+};
+
+/// The version of yagc, as served at `about:version`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+impl Client {
+    /// Describe the client's environment in a gemtext document, for including in bug reports, in the language of the client's catalog.
+    /// The same document is served at `about:diagnostics`.
+    pub fn diagnostics(&self) -> String {
+        let mut features = vec!["client"];
+        if cfg!(feature = "blocking") {
+            features.push("blocking");
+        }
+        if cfg!(feature = "tracing") {
+            features.push("tracing");
+        }
+
+        let (tofu_store_path, known_hosts) = {
+            let store = self.tofu_verifier.store();
+            (store.path().map(str::to_string), store.host_count())
+        };
+        let tofu_store_path = tofu_store_path.unwrap_or_else(|| self.text(Message::InMemory));
+        let cache = match &self.cache {
+            Some(cache) => {
+                let (responses, bytes) = cache.usage();
+                self.text(Message::CacheUsage { responses, bytes })
+            },
+            None => self.text(Message::Disabled),
+        };
+
+        let mut document = String::new();
+        document.push_str(&format!("# {}\n\n", self.text(Message::DiagnosticsTitle)));
+        document.push_str(&format!("* {}: {VERSION}\n", self.text(Message::Version)));
+        document.push_str(&format!("* {}: {}\n", self.text(Message::Features), features.join(", ")));
+        document.push_str(&format!(
+            "* {}: rustls, {}\n",
+            self.text(Message::Tls),
+            self.text(Message::TlsVersions { tls_1_3_only: self.require_tls_1_3 }),
+        ));
+        document.push_str(&format!(
+            "* {}: {tofu_store_path}, {}\n",
+            self.text(Message::TofuStore),
+            self.text(Message::KnownHosts { count: known_hosts }),
+        ));
+        document.push_str(&format!("* {}: {cache}\n", self.text(Message::ResponseCache)));
+        document.push_str(&format!("* {}: {}\n", self.text(Message::Offline), self.text(Message::YesNo(self.is_offline()))));
+
+        document
+    }
+
+    /// Answer a request for an `about:` URL. `about:version` and `about:diagnostics` are built in.
+    pub(super) fn about_page(&self, url: &URL) -> Response {
+        let body = match url.path.as_str() {
+            "version" => format!("yagc {VERSION}\n"),
+            "diagnostics" => self.diagnostics(),
+            _ => return Response::NotFound { information: self.text(Message::UnknownPage { url: url.to_string() }) },
+        };
+
+        Response::Success {
+            body_mime_type: MimeType::new("text/gemini", None, None),
+            body: body.into_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::ClientBuilder;
+    use crate::{response::Response, url::URL};
+
+    #[tokio::test]
+    async fn about_pages_are_served_locally() {
+        let client = ClientBuilder::new().in_memory_tofu_store().offline(true).build().unwrap();
+
+        let response = client.fetch(&URL::try_from("about:version").unwrap()).await.unwrap();
+        assert_eq!(response.text_strict().unwrap(), format!("yagc {}\n", super::VERSION));
+
+        let response = client.fetch(&URL::try_from("about:diagnostics").unwrap()).await.unwrap();
+        assert!(response.text_strict().unwrap().contains("* TOFU store: in memory, 0 known hosts"));
+
+        let response = client.fetch(&URL::try_from("about:meow").unwrap()).await.unwrap();
+        assert!(matches!(response, Response::NotFound { .. }));
+    }
+}
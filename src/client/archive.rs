@@ -0,0 +1,82 @@
+use super::{tofu::FingerprintAlgorithm, Client};
+
+/// A certificate fingerprint recorded alongside archived content, e.g. in a recorded session or a mirror.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecordedFingerprint {
+    /// The hostname the content was fetched from.
+    pub hostname: String,
+    /// The algorithm of the fingerprint.
+    pub algorithm: FingerprintAlgorithm,
+    /// The hex fingerprint of the certificate the content was fetched under.
+    pub fingerprint: String,
+}
+
+/// How a recorded fingerprint compares with the client's TOFU store.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FingerprintStatus {
+    /// The store has the same fingerprint for the host.
+    Match,
+    /// The store has a different fingerprint for the host, so the certificate has changed since the content was fetched.
+    Changed {
+        /// The fingerprint in the store.
+        current: String,
+    },
+    /// The store does not know the host.
+    Unknown,
+}
+
+impl FingerprintStatus {
+    fn of(known: Option<&String>, recorded: &str) -> Self {
+        match known {
+            Some(known) if known.eq_ignore_ascii_case(recorded) => Self::Match,
+            Some(known) => Self::Changed { current: known.clone() },
+            None => Self::Unknown,
+        }
+    }
+}
+
+impl Client {
+    /// Check recorded fingerprints against the TOFU store in bulk, returning a status for each in the same order.
+    /// No hosts are learned, so unknown hosts stay unknown, as do hosts with no fingerprint in the store under the record's algorithm.
+    pub fn verify_fingerprints(&self, records: &[RecordedFingerprint]) -> Vec<FingerprintStatus> {
+        let store = self.tofu_verifier.store();
+
+        records.iter()
+            .map(|record| FingerprintStatus::of(store.known_fingerprint(&record.hostname, record.algorithm), &record.fingerprint))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{authority::tests::LEAF_PEM, ClientBuilder};
+    use rustls::pki_types::{pem::PemObject, CertificateDer};
+
+    #[test]
+    fn fingerprint_status() {
+        let known = "ab12".to_string();
+
+        assert_eq!(FingerprintStatus::of(Some(&known), "ab12"), FingerprintStatus::Match);
+        assert_eq!(FingerprintStatus::of(Some(&known), "AB12"), FingerprintStatus::Match);
+        assert_eq!(FingerprintStatus::of(Some(&known), "cd34"), FingerprintStatus::Changed { current: known.clone() });
+        assert_eq!(FingerprintStatus::of(None, "ab12"), FingerprintStatus::Unknown);
+    }
+
+    #[test]
+    fn records_are_checked_under_their_algorithm() {
+        let client = ClientBuilder::new().in_memory_tofu_store().fingerprint_algorithm(FingerprintAlgorithm::Sha512).build().unwrap();
+        let leaf = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        client.tofu_verifier.replace_host("example.com", &leaf).unwrap();
+
+        let record = |algorithm: FingerprintAlgorithm| RecordedFingerprint {
+            hostname: "example.com".to_string(),
+            algorithm,
+            fingerprint: algorithm.fingerprint(&leaf),
+        };
+        let statuses = client.verify_fingerprints(&[record(FingerprintAlgorithm::Sha512), record(FingerprintAlgorithm::Sha256)]);
+
+        // the host was learned with SHA-512 only
+        assert_eq!(statuses, [FingerprintStatus::Match, FingerprintStatus::Unknown]);
+    }
+}
@@ -0,0 +1,126 @@
+use rustls::{
+    client::{danger::ServerCertVerifier, WebPkiServerVerifier},
+    pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime},
+    RootCertStore,
+};
+use std::sync::Arc;
+
+/// A private certificate authority, such as an organization's. Hosts it validates are trusted without TOFU.
+#[derive(Debug, Clone)]
+pub struct CertificateAuthority {
+    roots: Vec<CertificateDer<'static>>,
+    domains: Vec<String>,
+}
+
+impl CertificateAuthority {
+    /// Load the authority's root certificates from PEM. It applies to every host unless scoped with `domains`,
+    /// falling back to TOFU for certificates it doesn't validate.
+    pub fn from_pem(pem: impl AsRef<[u8]>) -> Result<Self, String> {
+        let roots = CertificateDer::pem_slice_iter(pem.as_ref())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read certificate: {e}"))?;
+        if roots.is_empty() {
+            return Err("No certificate found".to_string());
+        }
+
+        Ok(Self { roots, domains: Vec::new() })
+    }
+
+    /// Only use the authority for these domains and their subdomains.
+    /// Hosts in them must then be validated by the authority, and are never trusted on first use.
+    pub fn domains(mut self, domains: &[&str]) -> Self {
+        self.domains = domains.iter().map(|domain| domain.trim_end_matches('.').to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Get whether the authority applies to a hostname.
+    fn covers(&self, hostname: &str) -> bool {
+        let hostname = hostname.trim_end_matches('.').to_ascii_lowercase();
+
+        self.domains.is_empty() || self.domains.iter().any(|domain| {
+            hostname == *domain || hostname.strip_suffix(domain.as_str()).is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+    }
+}
+
+/// Verifies certificates against a `CertificateAuthority`'s roots with WebPKI.
+#[derive(Debug)]
+pub struct AuthorityVerifier {
+    authority: CertificateAuthority,
+    verifier: Arc<WebPkiServerVerifier>,
+}
+
+impl AuthorityVerifier {
+    pub fn new(authority: CertificateAuthority) -> Result<Self, String> {
+        let mut roots = RootCertStore::empty();
+        for root in &authority.roots {
+            roots.add(root.clone()).map_err(|e| format!("Invalid root certificate: {e}"))?;
+        }
+        let verifier = WebPkiServerVerifier::builder(Arc::new(roots)).build().map_err(|e| e.to_string())?;
+
+        Ok(Self { authority, verifier })
+    }
+
+    /// Verify a host's certificate against the authority, or get `None` if the authority doesn't apply to the host.
+    pub fn verify(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        hostname: &str,
+        now: UnixTime,
+    ) -> Option<Result<(), rustls::Error>> {
+        if !self.authority.covers(hostname) {
+            return None;
+        }
+
+        Some(self.verifier.verify_server_cert(end_entity, intermediates, server_name, &[], now).map(|_| ()))
+    }
+
+    /// Get whether the authority is scoped to domains, so the hosts it covers must be validated by it rather than trusted on first use.
+    pub fn is_scoped(&self) -> bool {
+        !self.authority.domains.is_empty()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // a root and a certificate for intranet.test it issued, generated for these tests
+    pub(crate) const ROOT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBlTCCATugAwIBAgIUbFLKgiluKrmHZ5df9IdwwgmOAvIwCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMeWFnYyB0ZXN0IENBMCAXDTI2MTAxNTA1NTAyMFoYDzIxMjYw
+OTIxMDU1MDIwWjAXMRUwEwYDVQQDDAx5YWdjIHRlc3QgQ0EwWTATBgcqhkjOPQIB
+BggqhkjOPQMBBwNCAAQ8NpGTHdJh5XBu3HS1qDaHagaqRh0gU/K07c74QVzWCE2Z
+6yNJDD+kR5spupScnAjFXaRLhnJI26eqhF+XMGGUo2MwYTAdBgNVHQ4EFgQUs60D
+C/94OozkNFQ7MhzWa7RupHowHwYDVR0jBBgwFoAUs60DC/94OozkNFQ7MhzWa7Ru
+pHowDwYDVR0TAQH/BAUwAwEB/zAOBgNVHQ8BAf8EBAMCAgQwCgYIKoZIzj0EAwID
+SAAwRQIgcL3kUZX7R58NtPIz6fMqGH6bfmh5F5c7F8e7uV0YDRMCIQDefz8eHLRu
+E/6K/S4LV1GSMdLVYJayw11RYGcJFR3GOg==
+-----END CERTIFICATE-----
+";
+    pub(crate) const LEAF_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBrjCCAVWgAwIBAgIUIv7EdoyxaQbkIUg/drw9TXD1w1owCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMeWFnYyB0ZXN0IENBMCAXDTI2MTAxNTA1NTAyMFoYDzIxMjYw
+OTIxMDU1MDIwWjAYMRYwFAYDVQQDDA1pbnRyYW5ldC50ZXN0MFkwEwYHKoZIzj0C
+AQYIKoZIzj0DAQcDQgAEwZg5E37g3F3iEalG5PbgbHXConf7t9xupSDz/D7bYfOK
+wm3HumLeWTsMTmBq7hKJbXPPsO8W7vHiJqgEfmDhDaN8MHowCQYDVR0TBAIwADAY
+BgNVHREEETAPgg1pbnRyYW5ldC50ZXN0MBMGA1UdJQQMMAoGCCsGAQUFBwMBMB0G
+A1UdDgQWBBS7PtY9rNVBs6ABeVWvpJxdTm9fsTAfBgNVHSMEGDAWgBSzrQML/3g6
+jOQ0VDsyHNZrtG6kejAKBggqhkjOPQQDAgNHADBEAiAqwMsuGBtysCsIeLAsRoI0
+uOm9y1q83x4Bp5wkCT9orQIgeo1uXrn1yF1MtVdjVBHSG/KCXfKSfFupHCswR5Bb
+m8k=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn authorities_are_scoped_to_their_domains() {
+        let authority = CertificateAuthority::from_pem(ROOT_PEM).unwrap().domains(&["Intranet.test"]);
+
+        assert!(authority.covers("intranet.test"));
+        assert!(authority.covers("gemini.intranet.test."));
+        assert!(!authority.covers("notintranet.test"));
+        assert!(CertificateAuthority::from_pem(ROOT_PEM).unwrap().covers("example.com"));
+    }
+}
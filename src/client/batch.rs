@@ -0,0 +1,148 @@
+use super::{join_all, Client, ClientError};
+use crate::{response::Response, url::URL};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+};
+
+/// The state of a batch job as saved to disk.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct BatchFile {
+    pending: Vec<String>,
+    completed: Vec<String>,
+}
+
+/// A batch of URLs to fetch whose progress is saved to a file, so an interrupted job can resume where it left off.
+#[derive(Debug)]
+pub struct BatchJob {
+    path: PathBuf,
+    pending: Vec<URL>,
+    completed: Vec<URL>,
+}
+
+impl BatchJob {
+    /// Create a new job fetching the URLs, saving its progress to the file at `path`.
+    pub fn new(path: impl AsRef<Path>, urls: &[URL]) -> Self {
+        Self { path: path.as_ref().to_path_buf(), pending: urls.to_vec(), completed: Vec::new() }
+    }
+
+    /// Load a job from the file its progress was saved to.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let path = path.as_ref();
+        let error = |e: String| ClientError::FailedToLoadBatchJob(format!("{}: {e}", path.display()));
+
+        let contents = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        let file: BatchFile = serde_json::from_str(&contents).map_err(|e| error(e.to_string()))?;
+        let parse = |urls: Vec<String>| {
+            urls.iter()
+                .map(|url| URL::try_from(url.as_str()))
+                .collect::<Result<Vec<URL>, String>>()
+                .map_err(error)
+        };
+
+        Ok(Self { path: path.to_path_buf(), pending: parse(file.pending)?, completed: parse(file.completed)? })
+    }
+
+    /// Load the job saved at `path` if there is one, or create a new job fetching the URLs otherwise.
+    pub fn resume_or_new(path: impl AsRef<Path>, urls: &[URL]) -> Result<Self, ClientError> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::new(path, urls))
+        }
+    }
+
+    /// Get the URLs that have not been fetched yet.
+    pub fn pending(&self) -> &[URL] {
+        &self.pending
+    }
+
+    /// Get the URLs that have been fetched, in the order they completed.
+    pub fn completed(&self) -> &[URL] {
+        &self.completed
+    }
+
+    /// Mark a URL as fetched.
+    fn complete(&mut self, url: &URL) {
+        if let Some(index) = self.pending.iter().position(|pending| pending == url) {
+            self.completed.push(self.pending.remove(index));
+        }
+    }
+
+    /// Save the job's progress, replacing the file in one step so a crash can't leave it half written.
+    fn save(&self) -> Result<(), ClientError> {
+        let file = BatchFile {
+            pending: self.pending.iter().map(URL::to_string).collect(),
+            completed: self.completed.iter().map(URL::to_string).collect(),
+        };
+        let error = |e: String| ClientError::FailedToSaveBatchJob(format!("{}: {e}", self.path.display()));
+
+        let contents = serde_json::to_string_pretty(&file).map_err(|e| error(e.to_string()))?;
+        let temporary_path = self.path.with_extension("tmp");
+        std::fs::write(&temporary_path, contents).map_err(|e| error(e.to_string()))?;
+        std::fs::rename(&temporary_path, &self.path).map_err(|e| error(e.to_string()))
+    }
+}
+
+impl Client {
+    /// Fetch a job's pending URLs, with at most `concurrency` fetches in flight at once, passing each result to `on_result`.
+    /// The job's file is saved after each fetch completes, so if the job is interrupted, loading it again resumes it.
+    pub async fn run_batch(
+        &self,
+        job: &mut BatchJob,
+        concurrency: usize,
+        on_result: impl FnMut(&URL, Result<Response, ClientError>) + Send,
+    ) -> Result<(), ClientError> {
+        job.save()?;
+
+        let urls = job.pending.clone();
+        let next_index = AtomicUsize::new(0);
+        let progress = Mutex::new((job, on_result));
+
+        // each worker takes the next unfetched URL until there are none left, stopping if progress can't be saved
+        let worker = || async {
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(url) = urls.get(index) else {
+                    return Ok(());
+                };
+
+                let result = self.fetch(url).await;
+
+                let mut progress = progress.lock().unwrap();
+                let (job, on_result) = &mut *progress;
+                job.complete(url);
+                job.save()?;
+                on_result(url, result);
+            }
+        };
+
+        join_all((0..concurrency.max(1)).map(|_| worker()))
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_round_trips_through_its_file() {
+        let path = std::env::temp_dir().join(format!("yagc_batch_test_{}.json", std::process::id()));
+        let urls = ["gemini://example.com/a", "gemini://example.com/b", "gemini://example.org/"]
+            .map(|url| URL::try_from(url).unwrap());
+
+        let mut job = BatchJob::new(&path, &urls);
+        job.complete(&urls[1]);
+        job.save().unwrap();
+
+        let job = BatchJob::resume_or_new(&path, &[]).unwrap();
+        assert_eq!(job.pending(), [urls[0].clone(), urls[2].clone()]);
+        assert_eq!(job.completed(), [urls[1].clone()]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
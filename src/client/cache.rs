@@ -0,0 +1,200 @@
+use crate::{response::Response, url::URL};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// A policy for caching successful responses in memory, so fetching the same page again doesn't hit the network.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CachePolicy {
+    /// How long a cached response is served for. Expired responses are kept until they are evicted, to serve in offline mode.
+    pub ttl: Duration,
+    /// The maximum total size of the cached bodies in bytes. The least recently used responses are evicted to stay under it.
+    pub max_size: usize,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_TTL,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: Response,
+    size: usize,
+    stored_at: Instant,
+    last_used: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: HashMap<String, Entry>, // identity fingerprint and URL -> entry
+    size: usize,
+}
+
+/// Get the key of a URL's response when fetched presenting the identity with the given fingerprint, if any.
+/// Responses are only served to requests presenting the same identity, since they may be specific to it.
+fn key(url: &URL, identity: Option<&str>) -> String {
+    match identity {
+        Some(fingerprint) => format!("{fingerprint} {url}"),
+        None => url.to_string(),
+    }
+}
+
+impl Entries {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// Caches successful responses according to a `CachePolicy`.
+#[derive(Debug)]
+pub struct ResponseCache {
+    policy: CachePolicy,
+    entries: Mutex<Entries>,
+}
+
+impl ResponseCache {
+    /// Create a new, empty cache following the given policy.
+    pub fn new(policy: &CachePolicy) -> Self {
+        Self { policy: policy.clone(), entries: Mutex::new(Entries::default()) }
+    }
+
+    /// Get the cached response for a URL fetched presenting the identity with the given fingerprint, if there is one that hasn't expired.
+    pub fn get(&self, url: &URL, identity: Option<&str>) -> Option<Response> {
+        self.get_at(url, identity, Instant::now())
+    }
+
+    /// Get the cached response for a URL like `get`, even if it has expired, for when the network can't be used.
+    pub fn get_stale(&self, url: &URL, identity: Option<&str>) -> Option<Response> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entries.get_mut(&key(url, identity))?;
+
+        entry.last_used = Instant::now();
+        Some(entry.response.clone())
+    }
+
+    /// Cache a response to a URL fetched presenting the identity with the given fingerprint, if it is a success small enough to fit.
+    pub fn insert(&self, url: &URL, identity: Option<&str>, response: &Response) {
+        self.insert_at(url, identity, response, Instant::now());
+    }
+
+    /// Get the number of cached responses and their total size in bytes.
+    pub fn usage(&self) -> (usize, usize) {
+        let entries = self.entries.lock().unwrap();
+
+        (entries.entries.len(), entries.size)
+    }
+
+    /// Remove every cached response.
+    pub fn clear(&self) {
+        *self.entries.lock().unwrap() = Entries::default();
+    }
+
+    fn get_at(&self, url: &URL, identity: Option<&str>, now: Instant) -> Option<Response> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let entry = entries.entries.get_mut(&key(url, identity))?;
+        if now.saturating_duration_since(entry.stored_at) >= self.policy.ttl {
+            return None;
+        }
+
+        entry.last_used = now;
+        Some(entry.response.clone())
+    }
+
+    fn insert_at(&self, url: &URL, identity: Option<&str>, response: &Response, now: Instant) {
+        let Response::Success { body, .. } = response else {
+            return;
+        };
+        let size = body.len();
+        if size > self.policy.max_size {
+            return;
+        }
+
+        let key = key(url, identity);
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+
+        while entries.size + size > self.policy.max_size {
+            // the loop only runs while there are entries taking up space
+            let least_recently_used = entries.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+                .unwrap();
+            entries.remove(&least_recently_used);
+        }
+
+        entries.size += size;
+        entries.entries.insert(key, Entry { response: response.clone(), size, stored_at: now, last_used: now });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::MimeType;
+
+    fn url(url: &str) -> URL {
+        URL::try_from(url).unwrap()
+    }
+
+    fn success(body: &str) -> Response {
+        Response::Success { body_mime_type: MimeType::new("text/gemini", None, None), body: body.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn responses_expire() {
+        let cache = ResponseCache::new(&CachePolicy { ttl: Duration::from_secs(60), ..Default::default() });
+        let now = Instant::now();
+
+        cache.insert_at(&url("gemini://example.com/"), None, &success("meow"), now);
+        cache.insert_at(&url("gemini://example.com/missing"), None, &Response::NotFound { information: "meow".to_string() }, now);
+
+        assert_eq!(cache.get_at(&url("gemini://example.com/"), None, now + Duration::from_secs(59)), Some(success("meow")));
+        assert_eq!(cache.get_at(&url("gemini://example.com/"), None, now + Duration::from_secs(60)), None);
+        assert_eq!(cache.get_stale(&url("gemini://example.com/"), None), Some(success("meow")));
+        assert_eq!(cache.get_at(&url("gemini://example.com/missing"), None, now), None);
+    }
+
+    #[test]
+    fn least_recently_used_responses_are_evicted() {
+        let cache = ResponseCache::new(&CachePolicy { max_size: 8, ..Default::default() });
+        let now = Instant::now();
+
+        cache.insert_at(&url("gemini://example.com/a"), None, &success("aaaa"), now);
+        cache.insert_at(&url("gemini://example.com/b"), None, &success("bbbb"), now + Duration::from_secs(1));
+        cache.get_at(&url("gemini://example.com/a"), None, now + Duration::from_secs(2));
+        cache.insert_at(&url("gemini://example.com/c"), None, &success("cccc"), now + Duration::from_secs(3));
+        cache.insert_at(&url("gemini://example.com/d"), None, &success("too big to cache"), now + Duration::from_secs(4));
+
+        let later = now + Duration::from_secs(5);
+        assert!(cache.get_at(&url("gemini://example.com/a"), None, later).is_some());
+        assert!(cache.get_at(&url("gemini://example.com/b"), None, later).is_none());
+        assert!(cache.get_at(&url("gemini://example.com/c"), None, later).is_some());
+        assert!(cache.get_at(&url("gemini://example.com/d"), None, later).is_none());
+    }
+
+    #[test]
+    fn responses_are_kept_apart_by_identity() {
+        let cache = ResponseCache::new(&CachePolicy::default());
+        let now = Instant::now();
+
+        cache.insert_at(&url("gemini://example.com/inbox"), Some("ab12"), &success("alice's mail"), now);
+
+        assert_eq!(cache.get_at(&url("gemini://example.com/inbox"), Some("ab12"), now), Some(success("alice's mail")));
+        assert_eq!(cache.get_at(&url("gemini://example.com/inbox"), Some("cd34"), now), None);
+        assert_eq!(cache.get_at(&url("gemini://example.com/inbox"), None, now), None);
+        assert_eq!(cache.get_stale(&url("gemini://example.com/inbox"), None), None);
+    }
+}
@@ -0,0 +1,172 @@
+use super::{tofu::FingerprintAlgorithm, Client, TlsConnection};
+use crate::url::Host;
+use std::{net::IpAddr, time::SystemTime};
+use x509_cert::{
+    der::{oid::ObjectIdentifier, Decode},
+    ext::pkix::{name::GeneralName, SubjectAltName},
+    Certificate,
+};
+
+const RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const P256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const P384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const P521: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.35");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const ED448: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.113");
+
+/// Whether a certificate is within its validity period.
+/// Self-signed capsule certificates are trusted on first use whatever their dates, so this is only worth warning about.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CertificateValidity {
+    /// The certificate is valid.
+    Valid,
+    /// The certificate's `notAfter` date has passed.
+    Expired,
+    /// The certificate's `notBefore` date hasn't come yet.
+    NotYetValid,
+}
+
+impl CertificateValidity {
+    /// Get whether a certificate valid from `not_before` until `not_after` is valid at `now`.
+    pub fn at(not_before: SystemTime, not_after: SystemTime, now: SystemTime) -> Self {
+        if now < not_before {
+            Self::NotYetValid
+        } else if now > not_after {
+            Self::Expired
+        } else {
+            Self::Valid
+        }
+    }
+}
+
+/// Details of a certificate, such as a capsule's, for showing to users.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PeerCertificate {
+    /// The hex-encoded SHA-256 fingerprint of the certificate, as it is usually shared.
+    pub fingerprint: String,
+    /// The certificate's subject, e.g. `CN=example.com`.
+    pub subject: String,
+    /// The DNS names, IP addresses, email addresses and URIs in the certificate's subject alternative names.
+    pub subject_alt_names: Vec<String>,
+    /// The time the certificate is valid from.
+    pub not_before: SystemTime,
+    /// The time the certificate is valid until.
+    pub not_after: SystemTime,
+    /// The algorithm of the certificate's public key, e.g. `RSA` or `EC P-256`. Unknown algorithms are given by their OID.
+    pub public_key_algorithm: String,
+}
+
+impl PeerCertificate {
+    /// Read the details of a DER-encoded certificate.
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let certificate = Certificate::from_der(der).map_err(|e| format!("Failed to read certificate: {e}"))?;
+        let certificate = certificate.tbs_certificate;
+
+        let subject_alt_names = match certificate.get::<SubjectAltName>() {
+            Ok(Some((_, SubjectAltName(names)))) => names.iter().filter_map(general_name).collect(),
+            Ok(None) => Vec::new(),
+            Err(e) => return Err(format!("Failed to read subject alternative names: {e}")),
+        };
+
+        let key_algorithm = &certificate.subject_public_key_info.algorithm;
+        let curve = key_algorithm.parameters.as_ref().and_then(|parameters| parameters.decode_as::<ObjectIdentifier>().ok());
+        let public_key_algorithm = match (key_algorithm.oid, curve) {
+            (RSA, _) => "RSA".to_string(),
+            (EC, Some(P256)) => "EC P-256".to_string(),
+            (EC, Some(P384)) => "EC P-384".to_string(),
+            (EC, Some(P521)) => "EC P-521".to_string(),
+            (EC, Some(curve)) => format!("EC {curve}"),
+            (ED25519, _) => "Ed25519".to_string(),
+            (ED448, _) => "Ed448".to_string(),
+            (oid, _) => oid.to_string(),
+        };
+
+        Ok(Self {
+            fingerprint: FingerprintAlgorithm::Sha256.fingerprint(der),
+            subject: certificate.subject.to_string(),
+            subject_alt_names,
+            not_before: certificate.validity.not_before.to_system_time(),
+            not_after: certificate.validity.not_after.to_system_time(),
+            public_key_algorithm,
+        })
+    }
+}
+
+/// Describe a subject alternative name, if it is of a kind users would recognize.
+fn general_name(name: &GeneralName) -> Option<String> {
+    match name {
+        GeneralName::DnsName(name) => Some(name.to_string()),
+        GeneralName::Rfc822Name(address) => Some(address.to_string()),
+        GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+        GeneralName::IpAddress(address) => match address.as_bytes().len() {
+            4 => <[u8; 4]>::try_from(address.as_bytes()).ok().map(|octets| IpAddr::from(octets).to_string()),
+            16 => <[u8; 16]>::try_from(address.as_bytes()).ok().map(|octets| IpAddr::from(octets).to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl TlsConnection {
+    /// Get the details of the certificate the server presented.
+    pub fn peer_certificate(&self) -> Result<PeerCertificate, String> {
+        let (_, connection) = self.stream.get_ref();
+        let certificate = connection.peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .ok_or_else(|| "The server presented no certificate".to_string())?;
+
+        PeerCertificate::from_der(certificate)
+    }
+
+    /// Get whether the certificate the server presented is valid now.
+    pub fn certificate_validity(&self) -> Result<CertificateValidity, String> {
+        let certificate = self.peer_certificate()?;
+
+        Ok(CertificateValidity::at(certificate.not_before, certificate.not_after, SystemTime::now()))
+    }
+}
+
+impl Client {
+    /// Get whether the certificate a host presented in its last handshake with the client is valid now,
+    /// or `None` if the client hasn't connected to the host.
+    pub fn certificate_validity(&self, host: &Host) -> Option<CertificateValidity> {
+        let (not_before, not_after) = self.tofu_verifier.validity_period(&host.name)?;
+
+        Some(CertificateValidity::at(not_before, not_after, SystemTime::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::authority::tests::LEAF_PEM;
+    use rustls::pki_types::{pem::PemObject, CertificateDer};
+    use std::time::Duration;
+
+    #[test]
+    fn certificate_details_are_read() {
+        let der = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        let certificate = PeerCertificate::from_der(&der).unwrap();
+
+        assert_eq!(certificate.fingerprint, FingerprintAlgorithm::Sha256.fingerprint(&der));
+        assert_eq!(certificate.subject, "CN=intranet.test");
+        assert_eq!(certificate.subject_alt_names, ["intranet.test"]);
+        assert_eq!(certificate.public_key_algorithm, "EC P-256");
+        // issued 2026-10-15 05:50:20 UTC
+        assert_eq!(certificate.not_before, SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_043_420));
+        assert!(certificate.not_after > certificate.not_before);
+
+        assert!(PeerCertificate::from_der(b"meow").is_err());
+    }
+
+    #[test]
+    fn validity_is_checked_against_both_dates() {
+        let not_before = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let not_after = not_before + Duration::from_secs(1_000);
+
+        assert_eq!(CertificateValidity::at(not_before, not_after, not_before + Duration::from_secs(1)), CertificateValidity::Valid);
+        assert_eq!(CertificateValidity::at(not_before, not_after, SystemTime::UNIX_EPOCH), CertificateValidity::NotYetValid);
+        assert_eq!(CertificateValidity::at(not_before, not_after, not_after + Duration::from_secs(1)), CertificateValidity::Expired);
+    }
+}
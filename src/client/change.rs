@@ -0,0 +1,44 @@
+use super::{Client, ClientError};
+use crate::{response::Response, url::URL};
+use sha2::{Digest, Sha256};
+
+/// The result of `Client::fetch_if_changed`.
+#[derive(Debug, PartialEq)]
+pub enum ChangeCheck {
+    /// The body has the same hash as before.
+    NotModified,
+    /// The response differs from before: a body with a different hash, or a response without a body.
+    Modified(Response),
+}
+
+impl Response {
+    /// Get the hex SHA-256 hash of the body, if the response has one.
+    pub fn body_hash(&self) -> Option<String> {
+        self.body().map(|body| hex::encode(Sha256::digest(body)))
+    }
+}
+
+impl Client {
+    /// Fetch a URL like `fetch`, but return `ChangeCheck::NotModified` instead of the response if its body hash is `previous_hash`.
+    pub async fn fetch_if_changed(&self, url: &URL, previous_hash: &str) -> Result<ChangeCheck, ClientError> {
+        let response = self.fetch(url).await?;
+
+        match response.body_hash() {
+            Some(hash) if hash.eq_ignore_ascii_case(previous_hash) => Ok(ChangeCheck::NotModified),
+            _ => Ok(ChangeCheck::Modified(response)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_hash() {
+        let response = Response::try_from("20 text/plain\r\nmeow").unwrap();
+
+        assert_eq!(response.body_hash(), Some("404cdd7bc109c432f8cc2443b45bcfe95980f5107215c645236e577929ac3e52".to_string()));
+        assert_eq!(Response::try_from("51 not found\r\n").unwrap().body_hash(), None);
+    }
+}
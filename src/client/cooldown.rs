@@ -0,0 +1,124 @@
+use crate::url::Host;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The cooldown used when a `SlowDown` response does not say how long to wait.
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(10);
+/// The longest cooldown a host can ask for by default.
+pub const DEFAULT_MAX_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// What the client does with a request to a host that asked it to slow down.
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum CooldownPolicy {
+    /// Wait until the cooldown has elapsed, then send the request.
+    #[default]
+    Wait,
+    /// Fail immediately with `ClientError::HostCoolingDown`.
+    Reject,
+}
+
+/// The cooldowns of hosts that have sent a `SlowDown` response.
+#[derive(Debug)]
+pub struct Cooldowns {
+    until: Mutex<HashMap<Host, Instant>>,
+    max: Duration,
+}
+
+impl Default for Cooldowns {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_COOLDOWN)
+    }
+}
+
+impl Cooldowns {
+    /// Create an empty set of cooldowns, none of which last longer than `max`.
+    pub fn new(max: Duration) -> Self {
+        Self { until: Mutex::new(HashMap::new()), max }
+    }
+
+    /// Start a cooldown for a host, given the meta of its `SlowDown` response.
+    /// The meta is read as a number of seconds to wait, falling back to a default if it isn't one.
+    /// Cooldowns longer than the maximum are cut short to it.
+    pub fn record(&self, host: &Host, information: &str) {
+        let duration = information.trim()
+            .parse::<u64>()
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_COOLDOWN)
+            .min(self.max);
+        let now = Instant::now();
+        // a maximum too far away to represent, such as `Duration::MAX`, is treated as no cooldown rather than panicking
+        let until = now.checked_add(duration).unwrap_or(now);
+
+        self.until.lock().unwrap().insert(host.clone(), until);
+    }
+
+    /// Get the time left on a host's cooldown, if it has one.
+    pub fn remaining(&self, host: &Host) -> Option<Duration> {
+        let mut until = self.until.lock().unwrap();
+        let remaining = until.get(host)?.checked_duration_since(Instant::now());
+
+        if remaining.is_none() {
+            until.remove(host);
+        }
+
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host() -> Host {
+        Host { name: "example.com".to_string(), port: 1965 }
+    }
+
+    #[test]
+    fn cooldown_from_meta() {
+        let cooldowns = Cooldowns::default();
+        cooldowns.record(&host(), "30");
+
+        let remaining = cooldowns.remaining(&host()).unwrap();
+        assert!(remaining > Duration::from_secs(29) && remaining <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn cooldown_without_seconds() {
+        let cooldowns = Cooldowns::default();
+        cooldowns.record(&host(), "please slow down");
+
+        assert!(cooldowns.remaining(&host()).unwrap() > DEFAULT_COOLDOWN - Duration::from_secs(1));
+    }
+
+    #[test]
+    fn cooldown_is_capped() {
+        let cooldowns = Cooldowns::new(Duration::from_secs(60));
+        cooldowns.record(&host(), "86400");
+
+        let remaining = cooldowns.remaining(&host()).unwrap();
+        assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn huge_cooldown_does_not_overflow() {
+        let cooldowns = Cooldowns::default();
+        cooldowns.record(&host(), &u64::MAX.to_string());
+        assert!(cooldowns.remaining(&host()).unwrap() <= DEFAULT_MAX_COOLDOWN);
+
+        let uncapped = Cooldowns::new(Duration::MAX);
+        uncapped.record(&host(), &u64::MAX.to_string());
+        assert_eq!(uncapped.remaining(&host()), None);
+    }
+
+    #[test]
+    fn expired_cooldown() {
+        let cooldowns = Cooldowns::default();
+        cooldowns.record(&host(), "0");
+
+        assert_eq!(cooldowns.remaining(&host()), None);
+        assert_eq!(cooldowns.remaining(&Host { name: "example.org".to_string(), port: 1965 }), None);
+    }
+}
@@ -0,0 +1,248 @@
+use super::{with_timeout, Client, TlsConnection, TlsProtocolVersion};
+use crate::{
+    request::Request,
+    url::{Host, URLBuilder},
+};
+use rustls::pki_types::ServerName;
+use std::time::{Duration, Instant};
+
+/// A stage of making a request to a host.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DiagnosticStage {
+    /// Resolving the host's addresses.
+    Resolution,
+    /// Opening a TCP connection to one of the addresses.
+    TcpConnection,
+    /// Asking the client's SOCKS5 proxy to connect to the host. Only run if the client has one.
+    ProxyConnection,
+    /// Performing the TLS handshake, including certificate verification.
+    TlsHandshake,
+    /// Sending a request and reading the response header.
+    GeminiResponse,
+}
+
+/// The outcome of one stage of a diagnosis.
+#[derive(Debug)]
+pub struct StageReport {
+    /// The stage this report is about.
+    pub stage: DiagnosticStage,
+    /// How long the stage took.
+    pub duration: Duration,
+    /// A description of what happened if the stage succeeded, or of why it failed.
+    pub result: Result<String, String>,
+}
+
+/// The result of diagnosing a host's reachability with `Client::diagnose`.
+#[derive(Debug)]
+pub struct Diagnosis {
+    /// The host that was diagnosed.
+    pub host: Host,
+    /// A report for each stage that was run, in order. Stages after a failed one are not run.
+    pub stages: Vec<StageReport>,
+}
+
+impl Diagnosis {
+    /// Get the report of the stage that failed, if any did.
+    pub fn failed_stage(&self) -> Option<&StageReport> {
+        self.stages.iter().find(|report| report.result.is_err())
+    }
+
+    /// Record the result of a stage, returning its value if it succeeded.
+    fn record<T>(&mut self, stage: DiagnosticStage, start: Instant, result: Result<(T, String), String>) -> Option<T> {
+        let duration = start.elapsed();
+        let (value, result) = match result {
+            Ok((value, description)) => (Some(value), Ok(description)),
+            Err(e) => (None, Err(e)),
+        };
+
+        self.stages.push(StageReport { stage, duration, result });
+
+        value
+    }
+}
+
+impl Client {
+    /// Diagnose problems reaching a host by running each stage of a request separately:
+    /// address resolution, TCP connection, TLS handshake and a request for the host's root.
+    /// The client's timeouts apply to each stage, and the TLS handshake verifies the host with the TOFU store as usual.
+    ///
+    /// Connections go the same way as `fetch`'s: with a SOCKS5 proxy, the proxy is resolved and connected to instead of the host,
+    /// and with a Gemini proxy, the TLS handshake is with the proxy.
+    pub async fn diagnose(&self, host: &Host) -> Diagnosis {
+        let mut diagnosis = Diagnosis { host: host.clone(), stages: Vec::new() };
+        // the host the TLS handshake is with, and the one the TCP connection is opened to
+        let tls_host = self.proxy.clone().unwrap_or_else(|| host.clone());
+        let tcp_host = self.socks5_proxy.as_ref().map_or(&tls_host, |proxy| &proxy.host);
+
+        let start = Instant::now();
+        let addresses = match with_timeout(self.timeouts.connect, self.resolve(&tcp_host.name, tcp_host.port)).await {
+            Ok(Ok(addresses)) => {
+                if addresses.is_empty() {
+                    Err("No addresses found".to_string())
+                } else {
+                    let description = addresses.iter()
+                        .map(|address| address.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+
+                    Ok((addresses, format!("Resolved to {description}")))
+                }
+            },
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err("Timed out".to_string()),
+        };
+        let Some(addresses) = diagnosis.record(DiagnosticStage::Resolution, start, addresses) else {
+            return diagnosis;
+        };
+
+        let start = Instant::now();
+        let mut tcp_connection = Err("No address accepted the connection".to_string());
+        for address in addresses {
+            tcp_connection = match with_timeout(self.timeouts.connect, self.connect_socket(address)).await {
+                Ok(Ok(tcp_stream)) => Ok(((tcp_stream, address), format!("Connected to {address}"))),
+                Ok(Err(e)) => Err(format!("{address}: {e}")),
+                Err(_) => Err(format!("{address}: Timed out")),
+            };
+
+            if tcp_connection.is_ok() {
+                break;
+            }
+        }
+        let Some((mut tcp_stream, peer_address)) = diagnosis.record(DiagnosticStage::TcpConnection, start, tcp_connection) else {
+            return diagnosis;
+        };
+
+        if let Some(proxy) = &self.socks5_proxy {
+            let start = Instant::now();
+            let proxy_connection = match with_timeout(self.timeouts.connect, proxy.connect(&mut tcp_stream, &tls_host.name, tls_host.port)).await {
+                Ok(Ok(())) => Ok(((), format!("Connected to {tls_host} through {}", proxy.host))),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err("Timed out".to_string()),
+            };
+            if diagnosis.record(DiagnosticStage::ProxyConnection, start, proxy_connection).is_none() {
+                return diagnosis;
+            }
+        }
+
+        let start = Instant::now();
+        let connector = match self.tls_connector(self.identity.as_ref(), None) {
+            Ok(connector) => connector,
+            Err(e) => {
+                diagnosis.record::<()>(DiagnosticStage::TlsHandshake, start, Err(format!("{e:?}")));
+                return diagnosis;
+            },
+        };
+        let tls_stream = match ServerName::try_from(tls_host.name.clone()) {
+            Ok(domain) => match with_timeout(self.timeouts.handshake, connector.connect(domain, tcp_stream)).await {
+                Ok(Ok(tls_stream)) => {
+                    let protocol_version = TlsProtocolVersion::of(&tls_stream);
+
+                    Ok((tls_stream, format!("Negotiated {protocol_version:?}")))
+                },
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("Timed out".to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        let Some(tls_stream) = diagnosis.record(DiagnosticStage::TlsHandshake, start, tls_stream) else {
+            return diagnosis;
+        };
+
+        let start = Instant::now();
+        let mut connection = TlsConnection {
+            protocol_version: TlsProtocolVersion::of(&tls_stream),
+            stream: tls_stream,
+            host: tls_host,
+            peer_address,
+            resolution_time: Duration::ZERO,
+        };
+        let url = URLBuilder::new().host(host.clone()).build();
+        let response = match self.send_request(Request(url), &mut connection).await {
+            Ok(response) => Ok(((), format!("Received status {}", response.status_code()))),
+            Err(e) => Err(format!("{e:?}")),
+        };
+        diagnosis.record(DiagnosticStage::GeminiResponse, start, response);
+
+        diagnosis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{resolve::StaticResolver, socks::Socks5Proxy, ClientBuilder};
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+    fn host() -> Host {
+        Host { name: "gemini.test".to_string(), port: 1965 }
+    }
+
+    // only the proxy can be resolved, so looking up the host itself would fail the diagnosis
+    fn resolver() -> StaticResolver {
+        StaticResolver::new().host("proxy.test", [IpAddr::V4(Ipv4Addr::LOCALHOST)])
+    }
+
+    fn stages(diagnosis: &Diagnosis) -> Vec<(DiagnosticStage, bool)> {
+        diagnosis.stages.iter().map(|report| (report.stage, report.result.is_ok())).collect()
+    }
+
+    #[tokio::test]
+    async fn diagnosis_goes_through_a_socks5_proxy() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0; 18];
+            stream.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[4..16], *b"\x0bgemini.test");
+            stream.write_all(&[5, 4, 0, 1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        });
+
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .resolver(resolver())
+            .socks5_proxy(Socks5Proxy::new(Host { name: "proxy.test".to_string(), port }))
+            .build()
+            .unwrap();
+        let diagnosis = client.diagnose(&host()).await;
+        server.await.unwrap();
+
+        assert_eq!(stages(&diagnosis), [
+            (DiagnosticStage::Resolution, true),
+            (DiagnosticStage::TcpConnection, true),
+            (DiagnosticStage::ProxyConnection, false),
+        ]);
+        assert!(diagnosis.failed_stage().unwrap().result.as_ref().unwrap_err().contains("host unreachable"));
+    }
+
+    #[tokio::test]
+    async fn diagnosis_goes_through_a_gemini_proxy() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // not a TLS server, so the handshake fails once the proxy has been reached
+        let server = tokio::spawn(async move {
+            listener.accept().await.unwrap();
+        });
+
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .resolver(resolver())
+            .proxy(Host { name: "proxy.test".to_string(), port })
+            .build()
+            .unwrap();
+        let diagnosis = client.diagnose(&host()).await;
+        server.await.unwrap();
+
+        assert_eq!(stages(&diagnosis), [
+            (DiagnosticStage::Resolution, true),
+            (DiagnosticStage::TcpConnection, true),
+            (DiagnosticStage::TlsHandshake, false),
+        ]);
+        assert_eq!(diagnosis.stages[1].result, Ok(format!("Connected to 127.0.0.1:{port}")));
+    }
+}
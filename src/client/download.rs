@@ -0,0 +1,136 @@
+use super::{with_timeout, Client, ClientError, CloseBehavior, TlsConnection};
+use crate::{request::Request, response::Response, url::URL};
+use std::{path::Path, time::Instant};
+use tokio::{fs::File, io::AsyncWriteExt};
+
+/// How much of the body is read at a time while downloading.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl Client {
+    /// Fetch a URL like `fetch`, but stream the body of a successful response to the file at `path` instead of keeping it in memory.
+    /// `on_progress` is called with the total number of body bytes received so far after each read.
+    /// The returned `Success` response has an empty body. Other responses are returned as they are, without creating the file.
+    /// Of the client's middleware, only `Middleware::on_redirect` runs, for each redirect followed.
+    pub async fn download(&self, url: &URL, path: impl AsRef<Path>, mut on_progress: impl FnMut(u64)) -> Result<Response, ClientError> {
+        let path = path.as_ref();
+
+        let download = async {
+            let mut visited = vec![url.clone()];
+
+            loop {
+                for target in self.remembered_redirects(visited.last().unwrap()) {
+                    self.redirect_policy.check(&target, &visited)?;
+                    self.before_redirect(visited.last().unwrap(), &target);
+                    visited.push(target);
+                }
+                let url = visited.last().unwrap();
+
+                if self.is_offline() {
+                    return Err(ClientError::OfflineMiss(url.without_query().to_string()));
+                }
+
+                let start = Instant::now();
+                let result = self.download_once(url, path, &mut on_progress).await;
+                self.record_request(url, &result, start.elapsed());
+                let response = result?;
+
+                match self.redirect_target(&response, &visited)? {
+                    Some(target) => {
+                        self.remember_redirect(&response, visited.last().unwrap(), &target, false)?;
+                        self.before_redirect(visited.last().unwrap(), &target);
+                        visited.push(target);
+                    },
+                    None => return Ok(response),
+                }
+            }
+        };
+
+        with_timeout(self.timeouts.total, download)
+            .await
+            .map_err(|_| ClientError::RequestTimeout(url.without_query().to_string()))?
+    }
+
+    /// Send a single request, streaming the body to the file if the response is a success.
+    async fn download_once(&self, url: &URL, path: &Path, on_progress: &mut impl FnMut(u64)) -> Result<Response, ClientError> {
+        // held until the download is done, so it counts towards the concurrency limit
+        let _permit = match (&self.scheduler, &url.host) {
+            (Some(scheduler), Some(host)) => Some(scheduler.acquire(host).await),
+            _ => None,
+        };
+
+        let mut connection = self.establish_tls_connection(url).await?;
+        self.write_request(&Request(url.clone()), &mut connection).await?;
+
+        let mut buffer = Vec::new();
+        let Some(body_start) = self.read_header(&mut connection, &mut buffer).await? else {
+            // the header is incomplete, so this fails with the reason
+            return Response::from_bytes_with_limit(&buffer, self.max_header_length).map_err(ClientError::InvalidResponseHeader);
+        };
+
+        let response = Response::from_bytes_with_limit(&buffer[..body_start], self.max_header_length)
+            .map_err(ClientError::InvalidResponseHeader)?;
+        self.record_cooldown(url, &response);
+
+        if !matches!(response, Response::Success { .. }) {
+            self.finish(&mut connection).await;
+            return Ok(response);
+        }
+
+        if let Err(e) = self.write_body(&mut connection, &buffer[body_start..], path, on_progress).await {
+            let _ = tokio::fs::remove_file(path).await;
+            return Err(e);
+        }
+
+        self.finish(&mut connection).await;
+        Ok(response)
+    }
+
+    /// Close the connection once the response has been read, if the client's close behavior says to.
+    async fn finish(&self, tls_connection: &mut TlsConnection) {
+        if self.close_behavior == CloseBehavior::AfterResponse {
+            self.close(tls_connection).await;
+        }
+    }
+
+    /// Write the body to the file, starting with the part read along with the header.
+    async fn write_body(
+        &self,
+        tls_connection: &mut TlsConnection,
+        start: &[u8],
+        path: &Path,
+        on_progress: &mut impl FnMut(u64),
+    ) -> Result<(), ClientError> {
+        let mut file = File::create(path)
+            .await
+            .map_err(|e| ClientError::FailedToWriteFile(e.to_string()))?;
+
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE.max(start.len()));
+        chunk.extend_from_slice(start);
+        let mut received = 0;
+
+        loop {
+            received += chunk.len() as u64;
+            if let Some(max_body_size) = self.max_body_size
+                && received > max_body_size as u64
+            {
+                return Err(self.abandon_response(tls_connection, max_body_size).await);
+            }
+
+            if !chunk.is_empty() {
+                file.write_all(&chunk)
+                    .await
+                    .map_err(|e| ClientError::FailedToWriteFile(e.to_string()))?;
+                on_progress(received);
+            }
+
+            chunk.clear();
+            if self.read_more(tls_connection, &mut chunk).await? == 0 {
+                break;
+            }
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| ClientError::FailedToWriteFile(e.to_string()))
+    }
+}
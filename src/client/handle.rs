@@ -0,0 +1,127 @@
+use super::{Client, ClientError};
+use crate::{response::Response, url::URL};
+use std::sync::{Arc, Mutex};
+use tokio::{sync::{watch, Semaphore}, task::JoinHandle};
+
+/// The status of a fetch started by `Client::spawn_all`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FetchStatus {
+    /// Waiting for one of the other fetches to finish.
+    Queued,
+    /// Being fetched.
+    Running,
+    /// Finished with a response or an error.
+    Finished,
+    /// Cancelled with `FetchHandle::cancel`.
+    Cancelled,
+}
+
+/// A handle to a fetch running in the background, returned by `Client::spawn_all`.
+/// Dropping the handle doesn't stop the fetch; use `cancel` for that.
+#[derive(Debug)]
+pub struct FetchHandle {
+    url: URL,
+    status: Arc<Mutex<FetchStatus>>,
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<Result<Response, ClientError>>,
+}
+
+impl FetchHandle {
+    /// Get the URL being fetched.
+    pub fn url(&self) -> &URL {
+        &self.url
+    }
+
+    /// Get the status of the fetch.
+    pub fn status(&self) -> FetchStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Stop the fetch. If it is queued, it never starts; if it is running, its connection is shut down cleanly.
+    /// Either way, it finishes with `ClientError::Cancelled`.
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Wait for the fetch to finish and get its result.
+    pub async fn join(self) -> Result<Response, ClientError> {
+        match self.task.await {
+            Ok(result) => result,
+            // the task is never aborted, so it can only fail by panicking
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+impl Client {
+    /// Fetch several URLs in background tasks like `fetch_all`, with at most `concurrency` fetches in flight at once,
+    /// returning a handle to each fetch in the same order as the URLs. Must be called within a tokio runtime.
+    pub fn spawn_all(self: &Arc<Self>, urls: &[URL], concurrency: usize) -> Vec<FetchHandle> {
+        let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+
+        urls.iter().map(|url| {
+            let status = Arc::new(Mutex::new(FetchStatus::Queued));
+            let (cancel, mut cancelled) = watch::channel(false);
+
+            let task = tokio::spawn({
+                let (client, url, status, permits) = (self.clone(), url.clone(), status.clone(), permits.clone());
+
+                async move {
+                    let set_status = |new_status| *status.lock().unwrap() = new_status;
+                    let cancellation = async move {
+                        // a dropped handle can't cancel the fetch any more
+                        if cancelled.wait_for(|cancelled| *cancelled).await.is_err() {
+                            std::future::pending::<()>().await;
+                        }
+                    };
+                    let mut cancellation = std::pin::pin!(cancellation);
+
+                    // held until the fetch is done, so it counts towards the concurrency limit
+                    let _permit = tokio::select! {
+                        biased;
+                        _ = &mut cancellation => {
+                            set_status(FetchStatus::Cancelled);
+                            return Err(ClientError::Cancelled(url.without_query().to_string()));
+                        },
+                        permit = permits.acquire() => permit,
+                    };
+
+                    set_status(FetchStatus::Running);
+                    let result = client.fetch_with_cancel(&url, cancellation).await;
+                    set_status(match result {
+                        Err(ClientError::Cancelled(_)) => FetchStatus::Cancelled,
+                        _ => FetchStatus::Finished,
+                    });
+
+                    result
+                }
+            });
+
+            FetchHandle { url: url.clone(), status, cancel, task }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientBuilder;
+
+    #[tokio::test]
+    async fn fetches_can_be_awaited_and_cancelled_individually() {
+        let client = Arc::new(ClientBuilder::new().in_memory_tofu_store().offline(true).build().unwrap());
+        let urls = ["about:version", "about:diagnostics"].map(|url| URL::try_from(url).unwrap());
+
+        let mut handles = client.spawn_all(&urls, 1);
+        assert!(handles.iter().all(|handle| handle.status() == FetchStatus::Queued));
+        handles[1].cancel();
+
+        let cancelled = handles.pop().unwrap();
+        let version = handles.pop().unwrap();
+        assert!(matches!(cancelled.join().await, Err(ClientError::Cancelled(_))));
+
+        let status = version.status.clone();
+        assert!(version.join().await.is_ok());
+        assert_eq!(*status.lock().unwrap(), FetchStatus::Finished);
+    }
+}
@@ -0,0 +1,318 @@
+use super::{Client, ClientError};
+use crate::{response::Response, url::{Host, URL}};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How often the statistics are saved while requests are being made. They are also saved when the client is dropped.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Aggregate statistics about the requests made to one host.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct HostStats {
+    /// The number of requests that got a response.
+    pub visits: u64,
+    /// The number of requests that failed without a response.
+    pub errors: u64,
+    /// The total time taken by the requests that got a response.
+    pub total_latency: Duration,
+    /// The status code of the last response.
+    pub last_status: Option<u8>,
+    /// The number of bytes of responses received.
+    pub bytes_received: u64,
+}
+
+impl HostStats {
+    /// Get the average time taken by the requests that got a response.
+    pub fn average_latency(&self) -> Option<Duration> {
+        let visits = u32::try_from(self.visits).ok().filter(|visits| *visits > 0)?;
+
+        Some(self.total_latency / visits)
+    }
+}
+
+/// Statistics for each host the client has made requests to, optionally kept in a file between runs.
+/// Changes are saved every so often rather than after each request, and when the store is dropped.
+#[derive(Debug)]
+pub struct HostStatsStore {
+    path: Option<PathBuf>, // none for an in-memory store
+    hosts: HashMap<Host, HostStats>,
+    generation: u64, // incremented by each change
+    snapshot_generation: u64, // the generation of the last snapshot taken for saving
+    last_snapshot: Instant,
+    saved_generation: Arc<Mutex<u64>>, // the generation in the file, locked while writing so saves land in order
+}
+
+impl Default for HostStatsStore {
+    fn default() -> Self {
+        Self {
+            path: None,
+            hosts: HashMap::new(),
+            generation: 0,
+            snapshot_generation: 0,
+            last_snapshot: Instant::now(),
+            saved_generation: Arc::new(Mutex::new(0)),
+        }
+    }
+}
+
+/// The contents of a `HostStatsStore` to be written to its file, taken so the write can happen without holding the store.
+#[derive(Debug)]
+pub(super) struct HostStatsSave {
+    path: PathBuf,
+    contents: String,
+    generation: u64,
+    saved_generation: Arc<Mutex<u64>>,
+}
+
+impl HostStatsSave {
+    /// Write the contents to the file, unless a later save has already been written.
+    fn write(self) -> Result<(), String> {
+        let mut saved_generation = self.saved_generation.lock().unwrap();
+        if *saved_generation >= self.generation {
+            return Ok(());
+        }
+
+        std::fs::write(&self.path, self.contents).map_err(|e| format!("Failed to write {}: {e}", self.path.display()))?;
+        *saved_generation = self.generation;
+
+        Ok(())
+    }
+
+    /// Write the contents to the file on a blocking thread, if there is a tokio runtime to run one on.
+    fn write_in_background(self) {
+        // statistics are best effort, and a failed save is retried with the next one
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(move || self.write())),
+            Err(_) => drop(self.write()),
+        }
+    }
+}
+
+impl HostStatsStore {
+    /// Create a store saved to the file at `path`, loading the statistics already in it if it exists.
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let stored: HashMap<String, HostStats> = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Failed to open {}: {e}", path.display())),
+        };
+        // hosts are stored as strings, since JSON keys must be
+        let hosts = stored.into_iter()
+            .map(|(host, stats)| Ok((Host::try_from(host.as_str()).map_err(|e| format!("Failed to read {}: {e}", path.display()))?, stats)))
+            .collect::<Result<_, String>>()?;
+
+        let mut store = Self::default();
+        store.path = Some(path);
+        store.hosts = hosts;
+
+        Ok(store)
+    }
+
+    /// Create an empty store that is never saved to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome of a request to a host.
+    fn record_request(&mut self, host: &Host, status_code: Option<u8>, latency: Duration) {
+        let stats = self.hosts.entry(host.clone()).or_default();
+        match status_code {
+            Some(status_code) => {
+                stats.visits += 1;
+                stats.total_latency += latency;
+                stats.last_status = Some(status_code);
+            },
+            None => stats.errors += 1,
+        }
+        self.generation += 1;
+    }
+
+    /// Record bytes received from a host.
+    fn record_bytes_received(&mut self, host: &Host, bytes: usize) {
+        self.hosts.entry(host.clone()).or_default().bytes_received += bytes as u64;
+        self.generation += 1;
+    }
+
+    /// Take a snapshot of the store to save, if it has a file and has changed since the last snapshot.
+    fn snapshot(&mut self) -> Result<Option<HostStatsSave>, String> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        if self.generation == self.snapshot_generation {
+            return Ok(None);
+        }
+
+        let stored: BTreeMap<String, &HostStats> = self.hosts.iter().map(|(host, stats)| (host.to_string(), stats)).collect();
+        let contents = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+        self.snapshot_generation = self.generation;
+        self.last_snapshot = Instant::now();
+
+        Ok(Some(HostStatsSave { path: path.clone(), contents, generation: self.generation, saved_generation: self.saved_generation.clone() }))
+    }
+
+    /// Take a snapshot of the store to save if it is time to save it again.
+    fn due_snapshot(&mut self) -> Option<HostStatsSave> {
+        if self.last_snapshot.elapsed() < SAVE_INTERVAL {
+            return None;
+        }
+
+        self.snapshot().ok().flatten()
+    }
+
+    /// Save the store to its file now, if it has one and has changed.
+    fn save(&mut self) -> Result<(), String> {
+        match self.snapshot()? {
+            Some(save) => save.write(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for HostStatsStore {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+impl Client {
+    /// Get the statistics for a host.
+    /// Always `None` unless the client was built with `ClientBuilder::record_host_stats`.
+    pub fn host_stats(&self, host: &Host) -> Option<HostStats> {
+        self.host_stats.as_ref()?.lock().unwrap().hosts.get(host).cloned()
+    }
+
+    /// Get the statistics for every host, most visited first.
+    pub fn all_host_stats(&self) -> Vec<(Host, HostStats)> {
+        let Some(store) = &self.host_stats else {
+            return Vec::new();
+        };
+
+        let mut hosts: Vec<(Host, HostStats)> = store.lock().unwrap().hosts.iter()
+            .map(|(host, stats)| (host.clone(), stats.clone()))
+            .collect();
+        hosts.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.visits));
+
+        hosts
+    }
+
+    /// Save the host statistics to their file now, rather than waiting for the next periodic save or for the client to be dropped.
+    /// Does nothing unless the client was built with `ClientBuilder::host_stats_store_path`.
+    pub fn save_host_stats(&self) -> Result<(), ClientError> {
+        let Some(store) = &self.host_stats else {
+            return Ok(());
+        };
+
+        // written without holding the store, so requests aren't held up by the disk
+        let save = store.lock().unwrap().snapshot().map_err(ClientError::FailedToSaveHostStats)?;
+        match save {
+            Some(save) => save.write().map_err(ClientError::FailedToSaveHostStats),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the outcome of a request in the client's metrics and host statistics.
+    pub(super) fn record_request(&self, url: &URL, result: &Result<Response, ClientError>, latency: Duration) {
+        self.metrics.record_request(url, result, latency);
+
+        if let (Some(store), Some(host)) = (&self.host_stats, &url.host) {
+            let status_code = result.as_ref().ok().map(Response::status_code);
+            let save = {
+                let mut store = store.lock().unwrap();
+                store.record_request(host, status_code, latency);
+                store.due_snapshot()
+            };
+
+            if let Some(save) = save {
+                save.write_in_background();
+            }
+        }
+    }
+
+    /// Record bytes received from a host in the client's metrics and host statistics.
+    pub(super) fn record_bytes_received(&self, host: &Host, bytes: usize) {
+        self.metrics.record_bytes_received(host, bytes);
+
+        if let Some(store) = &self.host_stats {
+            store.lock().unwrap().record_bytes_received(host, bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_round_trips_through_its_file() {
+        let path = std::env::temp_dir().join(format!("yagc_host_stats_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let host = Host { name: "example.com".to_string(), port: 1965 };
+
+        let mut store = HostStatsStore::new(path.clone()).unwrap();
+        store.record_bytes_received(&host, 100);
+        store.record_request(&host, Some(20), Duration::from_millis(100));
+        store.record_request(&host, Some(51), Duration::from_millis(300));
+        store.record_request(&host, None, Duration::from_secs(10));
+        // saved when dropped
+        drop(store);
+
+        let store = HostStatsStore::new(path.clone()).unwrap();
+        let stats = &store.hosts[&host];
+        assert_eq!(stats.visits, 2);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.average_latency(), Some(Duration::from_millis(200)));
+        assert_eq!(stats.last_status, Some(51));
+        assert_eq!(stats.bytes_received, 100);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn ipv6_hosts_round_trip_through_the_file() {
+        let path = std::env::temp_dir().join(format!("yagc_host_stats_ipv6_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let host = Host::try_from("[::1]").unwrap();
+
+        let mut store = HostStatsStore::new(path.clone()).unwrap();
+        store.record_request(&host, Some(20), Duration::from_millis(100));
+        drop(store);
+
+        assert_eq!(HostStatsStore::new(path.clone()).unwrap().hosts[&host].visits, 1);
+
+        // a host that can't be read back is an error rather than being dropped
+        std::fs::write(&path, r#"{ ":1965": { "visits": 1, "errors": 0, "total_latency": { "secs": 0, "nanos": 0 }, "last_status": 20, "bytes_received": 0 } }"#).unwrap();
+        assert!(HostStatsStore::new(path.clone()).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn saves_land_in_order() {
+        let path = std::env::temp_dir().join(format!("yagc_host_stats_order_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let host = Host { name: "example.com".to_string(), port: 1965 };
+
+        let mut store = HostStatsStore::new(path.clone()).unwrap();
+        store.record_request(&host, Some(20), Duration::from_millis(100));
+        assert!(store.due_snapshot().is_none());
+        assert!(!path.exists());
+
+        let older = store.snapshot().unwrap().unwrap();
+        assert!(store.snapshot().unwrap().is_none());
+        store.record_request(&host, Some(51), Duration::from_millis(100));
+        let newer = store.snapshot().unwrap().unwrap();
+
+        // a save that was overtaken doesn't overwrite the later one
+        newer.write().unwrap();
+        older.write().unwrap();
+        drop(store);
+        assert_eq!(HostStatsStore::new(path.clone()).unwrap().hosts[&host].last_status, Some(51));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
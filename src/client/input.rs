@@ -0,0 +1,52 @@
+use super::{redirect::FetchResult, Client, ClientError};
+use crate::{response::Response, url::URL};
+use std::future::Future;
+
+/// A request for input from a server, passed to the callback of `Client::fetch_with_input`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InputPrompt {
+    /// The URL that asked for input. The answer is sent as this URL's query.
+    pub url: URL,
+    /// The prompt to show to the user.
+    pub prompt: String,
+    /// Whether the input is sensitive (such as a password), and so shouldn't be echoed.
+    pub sensitive: bool,
+}
+
+impl Client {
+    /// Fetch a URL, answering each `Input`/`SensitiveInput` response with `answer` and requesting the URL again with the answer as its query.
+    /// Returns the first other response, or the request for input itself if `answer` returns `None`.
+    /// Requests carrying sensitive input are not retried unless the retry policy's `retry_sensitive_input` is set.
+    pub async fn fetch_with_input<F, Fut>(&self, url: &URL, mut answer: F) -> Result<Response, ClientError>
+    where
+        F: FnMut(InputPrompt) -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        let mut url = url.clone();
+        let mut sensitive = false;
+
+        loop {
+            let FetchResult { response, url: final_url, .. } = self.fetch_chain(&url, sensitive, std::future::pending()).await?;
+
+            let (prompt, is_sensitive) = match &response {
+                Response::Input { prompt } => (prompt.clone(), false),
+                Response::SensitiveInput { prompt } => (prompt.clone(), true),
+                _ => return Ok(response),
+            };
+
+            let Some(input) = answer(InputPrompt { url: final_url.clone(), prompt, sensitive: is_sensitive }).await else {
+                return Ok(response);
+            };
+
+            url = final_url.with_query(&input);
+            sensitive = is_sensitive;
+        }
+    }
+
+    /// Fetch a URL like `fetch_with_input`, answering the first request for input with `input`.
+    pub async fn fetch_with_answer(&self, url: &URL, input: &str) -> Result<Response, ClientError> {
+        let mut input = Some(input.to_string());
+
+        self.fetch_with_input(url, |_| std::future::ready(input.take())).await
+    }
+}
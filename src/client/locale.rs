@@ -0,0 +1,124 @@
+use super::Client;
+use std::sync::Arc;
+
+/// A piece of text the client generates for users, such as a line of the `about:diagnostics` page.
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
+pub enum Message {
+    /// The heading of the diagnostics page.
+    DiagnosticsTitle,
+    /// The label of the version line.
+    Version,
+    /// The label of the features line.
+    Features,
+    /// The label of the TLS line.
+    Tls,
+    /// The TLS versions the client accepts.
+    TlsVersions {
+        /// Whether only TLS 1.3 is accepted.
+        tls_1_3_only: bool,
+    },
+    /// The label of the TOFU store line.
+    TofuStore,
+    /// Where the TOFU store is kept when it has no file.
+    InMemory,
+    /// How many hosts the TOFU store knows.
+    KnownHosts {
+        /// The number of hosts.
+        count: usize,
+    },
+    /// The label of the response cache line.
+    ResponseCache,
+    /// How much of the response cache is used.
+    CacheUsage {
+        /// The number of responses cached.
+        responses: usize,
+        /// The total size of the cached responses.
+        bytes: usize,
+    },
+    /// Said of a feature that is turned off.
+    Disabled,
+    /// The label of the offline line.
+    Offline,
+    /// A yes or no answer.
+    YesNo(bool),
+    /// The information sent with a request for an `about:` page that doesn't exist.
+    UnknownPage {
+        /// The URL requested.
+        url: String,
+    },
+}
+
+impl Message {
+    /// Get the message in English, which is used when the client's catalog doesn't translate it.
+    pub fn english(&self) -> String {
+        match self {
+            Self::DiagnosticsTitle => "yagc diagnostics".to_string(),
+            Self::Version => "Version".to_string(),
+            Self::Features => "Features".to_string(),
+            Self::Tls => "TLS".to_string(),
+            Self::TlsVersions { tls_1_3_only: true } => "TLS 1.3 only".to_string(),
+            Self::TlsVersions { tls_1_3_only: false } => "TLS 1.2 and 1.3".to_string(),
+            Self::TofuStore => "TOFU store".to_string(),
+            Self::InMemory => "in memory".to_string(),
+            Self::KnownHosts { count } => format!("{count} known hosts"),
+            Self::ResponseCache => "Response cache".to_string(),
+            Self::CacheUsage { responses, bytes } => format!("{responses} responses, {bytes} bytes"),
+            Self::Disabled => "disabled".to_string(),
+            Self::Offline => "Offline".to_string(),
+            Self::YesNo(true) => "yes".to_string(),
+            Self::YesNo(false) => "no".to_string(),
+            Self::UnknownPage { url } => format!("Unknown page {url}"),
+        }
+    }
+}
+
+/// Translates the text the client generates. Set it with `ClientBuilder::catalog`.
+pub trait Catalog: Send + Sync {
+    /// Get a message in the catalog's language, or `None` to fall back to English.
+    fn translate(&self, message: &Message) -> Option<String>;
+}
+
+// so a catalog can be shared with the code that set it
+impl<T: Catalog + ?Sized> Catalog for Arc<T> {
+    fn translate(&self, message: &Message) -> Option<String> {
+        (**self).translate(message)
+    }
+}
+
+impl Client {
+    /// Get a message in the language of the client's catalog.
+    pub(super) fn text(&self, message: Message) -> String {
+        self.catalog.as_ref()
+            .and_then(|catalog| catalog.translate(&message))
+            .unwrap_or_else(|| message.english())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientBuilder;
+
+    struct French;
+
+    impl Catalog for French {
+        fn translate(&self, message: &Message) -> Option<String> {
+            match message {
+                Message::TofuStore => Some("Magasin TOFU".to_string()),
+                Message::InMemory => Some("en mémoire".to_string()),
+                Message::KnownHosts { count } => Some(format!("{count} hôtes connus")),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn untranslated_messages_fall_back_to_english() {
+        let client = ClientBuilder::new().in_memory_tofu_store().catalog(French).build().unwrap();
+        let diagnostics = client.diagnostics();
+
+        assert!(diagnostics.contains("* Magasin TOFU: en mémoire, 0 hôtes connus\n"));
+        assert!(diagnostics.contains("* Offline: no\n"));
+    }
+}
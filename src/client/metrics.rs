@@ -0,0 +1,219 @@
+use super::ClientError;
+use crate::{response::Response, url::{Host, URL}};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The upper bounds of the buckets of a `LatencyHistogram`. Latencies above the last bound go in a final overflow bucket.
+pub const LATENCY_BUCKETS: [Duration; 9] = [
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// A histogram of request latencies.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LatencyHistogram {
+    /// The number of latencies in each bucket of `LATENCY_BUCKETS`, followed by the overflow bucket.
+    pub counts: [u64; LATENCY_BUCKETS.len() + 1],
+    /// The sum of all latencies recorded.
+    pub sum: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self { counts: [0; LATENCY_BUCKETS.len() + 1], sum: Duration::ZERO }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS.iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.counts[bucket] += 1;
+        self.sum += latency;
+    }
+}
+
+/// Metrics about the requests made to one host.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct HostMetrics {
+    /// The number of requests that got a response.
+    pub responses: u64,
+    /// The number of requests that failed without a response.
+    pub errors: u64,
+    /// The number of responses with each status code.
+    pub status_codes: BTreeMap<u8, u64>,
+}
+
+/// A snapshot of a client's metrics, from `Client::metrics`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ClientMetrics {
+    /// The number of requests made, including retries and redirects.
+    pub requests: u64,
+    /// The number of requests that failed without a response.
+    pub errors: u64,
+    /// The number of bytes of requests sent.
+    pub bytes_sent: u64,
+    /// The number of bytes of responses received.
+    pub bytes_received: u64,
+    /// How fast responses have been read over about the last second, in bytes per second.
+    pub throughput: f64,
+    /// How long requests took, including any wait for a cooldown or the politeness policy.
+    pub latency: LatencyHistogram,
+    /// Metrics for each host requests were made to.
+    pub hosts: HashMap<Host, HostMetrics>,
+}
+
+/// The outcome of a single request, passed to a `MetricsRecorder`.
+#[derive(Debug)]
+pub struct RequestRecord<'a> {
+    /// The URL requested, without its query, which may hold input like passwords.
+    pub url: &'a URL,
+    /// The status code of the response, or `None` if the request failed.
+    pub status_code: Option<u8>,
+    /// How long the request took.
+    pub latency: Duration,
+}
+
+/// Receives the outcome of each request as it completes, for exporting metrics to systems like Prometheus.
+pub trait MetricsRecorder: Send + Sync {
+    /// Record the outcome of a request.
+    fn record_request(&self, record: &RequestRecord);
+
+    /// Record bytes sent to a host.
+    fn record_bytes_sent(&self, _host: &Host, _bytes: usize) {}
+
+    /// Record bytes received from a host.
+    fn record_bytes_received(&self, _host: &Host, _bytes: usize) {}
+}
+
+/// Collects a client's metrics.
+#[derive(Default)]
+pub struct Metrics {
+    snapshot: Mutex<ClientMetrics>,
+    recorder: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl Metrics {
+    /// Create a new collector, passing each event on to the recorder if there is one.
+    pub fn new(recorder: Option<Arc<dyn MetricsRecorder>>) -> Self {
+        Self { snapshot: Mutex::new(ClientMetrics::default()), recorder }
+    }
+
+    /// Get a copy of the metrics collected so far.
+    pub fn snapshot(&self) -> ClientMetrics {
+        self.snapshot.lock().unwrap().clone()
+    }
+
+    /// Record the outcome of a request.
+    pub fn record_request(&self, url: &URL, result: &Result<Response, ClientError>, latency: Duration) {
+        let status_code = result.as_ref().ok().map(Response::status_code);
+
+        {
+            let mut snapshot = self.snapshot.lock().unwrap();
+            snapshot.requests += 1;
+            snapshot.latency.record(latency);
+            if status_code.is_none() {
+                snapshot.errors += 1;
+            }
+
+            if let Some(host) = &url.host {
+                let host = snapshot.hosts.entry(host.clone()).or_default();
+                match status_code {
+                    Some(status_code) => {
+                        host.responses += 1;
+                        *host.status_codes.entry(status_code).or_default() += 1;
+                    },
+                    None => host.errors += 1,
+                }
+            }
+        }
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_request(&RequestRecord { url: &url.without_query(), status_code, latency });
+        }
+    }
+
+    /// Record bytes sent to a host.
+    pub fn record_bytes_sent(&self, host: &Host, bytes: usize) {
+        self.snapshot.lock().unwrap().bytes_sent += bytes as u64;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_bytes_sent(host, bytes);
+        }
+    }
+
+    /// Record bytes received from a host.
+    pub fn record_bytes_received(&self, host: &Host, bytes: usize) {
+        self.snapshot.lock().unwrap().bytes_received += bytes as u64;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_bytes_received(host, bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_buckets() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(Duration::from_millis(5));
+        histogram.record(Duration::from_millis(10));
+        histogram.record(Duration::from_millis(300));
+        histogram.record(Duration::from_secs(60));
+
+        assert_eq!(histogram.counts, [2, 0, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(histogram.sum, Duration::from_millis(60315));
+    }
+
+    #[test]
+    fn requests_are_counted_per_host() {
+        let metrics = Metrics::default();
+        let url = URL::try_from("gemini://example.com/").unwrap();
+
+        metrics.record_request(&url, &Ok(Response::NotFound { information: "meow".to_string() }), Duration::from_millis(20));
+        metrics.record_request(&url, &Ok(Response::NotFound { information: "meow".to_string() }), Duration::from_millis(20));
+        metrics.record_request(&url, &Err(ClientError::ConnectTimeout("example.com".to_string())), Duration::from_secs(10));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.requests, 3);
+        assert_eq!(snapshot.errors, 1);
+
+        let host = &snapshot.hosts[url.host.as_ref().unwrap()];
+        assert_eq!(host.responses, 2);
+        assert_eq!(host.errors, 1);
+        assert_eq!(host.status_codes, BTreeMap::from([(51, 2)]));
+    }
+
+    #[test]
+    fn recorders_get_urls_without_their_query() {
+        struct Urls(Mutex<Vec<String>>);
+
+        impl MetricsRecorder for Urls {
+            fn record_request(&self, record: &RequestRecord) {
+                self.0.lock().unwrap().push(record.url.to_string());
+            }
+        }
+
+        let urls = Arc::new(Urls(Mutex::new(Vec::new())));
+        let metrics = Metrics::new(Some(urls.clone()));
+        let url = URL::try_from("gemini://example.com/login?hunter2").unwrap();
+        metrics.record_request(&url, &Ok(Response::NotFound { information: "meow".to_string() }), Duration::from_millis(20));
+
+        assert_eq!(*urls.0.lock().unwrap(), ["gemini://example.com:1965/login"]);
+    }
+}
@@ -0,0 +1,129 @@
+use super::{Client, ClientError};
+use crate::{response::Response, url::URL};
+use std::sync::Arc;
+
+/// Hooks into each request made by `Client::fetch` and the methods built on it, for logging, caching or rewriting requests.
+/// Every method does nothing by default. Middleware runs in the order it was added to the `ClientBuilder`.
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent. The URL can be rewritten, or a response returned to use instead of sending the request.
+    fn on_request(&self, _url: &mut URL) -> Option<Response> {
+        None
+    }
+
+    /// Called with each response received, or returned by `on_request`.
+    fn on_response(&self, _url: &URL, _response: &mut Response) {}
+
+    /// Called before following a redirect the client's redirect policy allows, including by `Client::download`.
+    fn on_redirect(&self, _from: &URL, _to: &URL) {}
+
+    /// Called when a request fails.
+    fn on_error(&self, _url: &URL, _error: &ClientError) {}
+}
+
+// so middleware can be shared with the code that added it
+impl<T: Middleware + ?Sized> Middleware for Arc<T> {
+    fn on_request(&self, url: &mut URL) -> Option<Response> {
+        (**self).on_request(url)
+    }
+
+    fn on_response(&self, url: &URL, response: &mut Response) {
+        (**self).on_response(url, response)
+    }
+
+    fn on_redirect(&self, from: &URL, to: &URL) {
+        (**self).on_redirect(from, to)
+    }
+
+    fn on_error(&self, url: &URL, error: &ClientError) {
+        (**self).on_error(url, error)
+    }
+}
+
+impl Client {
+    /// Run the client's middleware before a request, returning the response to use instead of sending it, if any.
+    pub(super) fn before_request(&self, url: &mut URL) -> Option<Response> {
+        self.middleware.iter().find_map(|middleware| middleware.on_request(url))
+    }
+
+    /// Run the client's middleware on the result of a request.
+    pub(super) fn after_request(&self, url: &URL, result: Result<Response, ClientError>) -> Result<Response, ClientError> {
+        match result {
+            Ok(mut response) => {
+                for middleware in &self.middleware {
+                    middleware.on_response(url, &mut response);
+                }
+
+                Ok(response)
+            },
+            Err(e) => Err(self.report_error(url, e)),
+        }
+    }
+
+    /// Run the client's middleware on an error, returning the error.
+    pub(super) fn report_error(&self, url: &URL, error: ClientError) -> ClientError {
+        for middleware in &self.middleware {
+            middleware.on_error(url, &error);
+        }
+
+        error
+    }
+
+    /// Run the client's middleware before following a redirect.
+    pub(super) fn before_redirect(&self, from: &URL, to: &URL) {
+        for middleware in &self.middleware {
+            middleware.on_redirect(from, to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ClientBuilder;
+    use std::sync::Mutex;
+
+    /// Answers every request itself and records what it saw.
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Middleware for Recorder {
+        fn on_request(&self, url: &mut URL) -> Option<Response> {
+            self.events.lock().unwrap().push(format!("request {url}"));
+
+            match url.path.as_str() {
+                "/old" => Some(Response::PermanentRedirect { url: "gemini://example.com/new".to_string() }),
+                _ => Some(Response::NotFound { information: "meow".to_string() }),
+            }
+        }
+
+        fn on_response(&self, url: &URL, response: &mut Response) {
+            self.events.lock().unwrap().push(format!("response {url} {}", response.status_code()));
+        }
+
+        fn on_redirect(&self, from: &URL, to: &URL) {
+            self.events.lock().unwrap().push(format!("redirect {from} {to}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_sees_each_hop() {
+        let recorder = Arc::new(Recorder { events: Mutex::new(Vec::new()) });
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .middleware(recorder.clone())
+            .build()
+            .unwrap();
+
+        let response = client.fetch(&URL::try_from("gemini://example.com/old").unwrap()).await.unwrap();
+
+        assert_eq!(response.status_code(), 51);
+        assert_eq!(*recorder.events.lock().unwrap(), vec![
+            "request gemini://example.com:1965/old",
+            "response gemini://example.com:1965/old 31",
+            "redirect gemini://example.com:1965/old gemini://example.com:1965/new",
+            "request gemini://example.com:1965/new",
+            "response gemini://example.com:1965/new 51",
+        ]);
+    }
+}
@@ -0,0 +1,318 @@
+use super::{Client, ClientError};
+use crate::{response::Response, url::URL};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Permanent redirects the client has followed, so later requests for the old URLs go straight to the new ones.
+#[derive(Debug, Default)]
+pub struct PermanentRedirects {
+    path: Option<PathBuf>, // none for an in-memory store
+    redirects: BTreeMap<String, String>, // old URL -> new URL
+}
+
+impl PermanentRedirects {
+    /// Create a store saved to the file at `path`, loading the redirects already in it if it exists.
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let redirects = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Failed to open {}: {e}", path.display())),
+        };
+
+        Ok(Self { path: Some(path), redirects })
+    }
+
+    /// Create an empty store that is never saved to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Get each URL a URL permanently redirects through in turn, following redirects between remembered URLs.
+    fn resolve(&self, url: &URL) -> Vec<URL> {
+        let mut visited = vec![url.to_string()];
+
+        while let Some(target) = self.redirects.get(visited.last().unwrap()) {
+            // a cycle of permanent redirects can't be resolved
+            if visited.contains(target) {
+                return Vec::new();
+            }
+
+            visited.push(target.clone());
+        }
+
+        visited[1..].iter()
+            .map(|target| URL::try_from(target.as_str()))
+            .collect::<Result<_, _>>()
+            .unwrap_or_default()
+    }
+
+    /// Remember that `from` permanently redirects to `to`.
+    fn insert(&mut self, from: &URL, to: &URL) -> Result<(), String> {
+        self.redirects.insert(from.to_string(), to.to_string());
+
+        self.save()
+    }
+
+    /// Forget the permanent redirect from `from`, if there is one.
+    fn remove(&mut self, from: &URL) -> Result<(), String> {
+        if self.redirects.remove(&from.to_string()).is_some() {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// Forget every permanent redirect.
+    fn clear(&mut self) -> Result<(), String> {
+        self.redirects.clear();
+
+        self.save()
+    }
+
+    /// Save the store to its file, if it has one.
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let contents = serde_json::to_string_pretty(&self.redirects).map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+impl Client {
+    /// Get the permanent redirects the client remembers, as pairs of the old and new URLs.
+    /// Empty unless the client was built with `ClientBuilder::remember_permanent_redirects`.
+    pub fn permanent_redirects(&self) -> Vec<(URL, URL)> {
+        let Some(store) = &self.permanent_redirects else {
+            return Vec::new();
+        };
+
+        store.lock().unwrap().redirects.iter()
+            .filter_map(|(from, to)| Some((URL::try_from(from.as_str()).ok()?, URL::try_from(to.as_str()).ok()?)))
+            .collect()
+    }
+
+    /// Forget the permanent redirect from a URL, so the next request for it goes to the URL itself again.
+    pub fn forget_permanent_redirect(&self, url: &URL) -> Result<(), ClientError> {
+        match &self.permanent_redirects {
+            Some(store) => store.lock().unwrap().remove(url).map_err(ClientError::FailedToSavePermanentRedirects),
+            None => Ok(()),
+        }
+    }
+
+    /// Forget every permanent redirect the client remembers.
+    pub fn clear_permanent_redirects(&self) -> Result<(), ClientError> {
+        match &self.permanent_redirects {
+            Some(store) => store.lock().unwrap().clear().map_err(ClientError::FailedToSavePermanentRedirects),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the URLs `url` is known to permanently redirect through, ending with the one to request instead.
+    /// Each must still pass the client's redirect policy, like a redirect the host sent.
+    pub(super) fn remembered_redirects(&self, url: &URL) -> Vec<URL> {
+        match &self.permanent_redirects {
+            Some(store) => store.lock().unwrap().resolve(url),
+            None => Vec::new(),
+        }
+    }
+
+    /// Remember a redirect from `from` to `to` if the response was a permanent redirect and the client remembers them.
+    /// Redirects followed while fetching a URL carrying sensitive input aren't remembered, so the input is never saved with them.
+    pub(super) fn remember_redirect(&self, response: &Response, from: &URL, to: &URL, sensitive: bool) -> Result<(), ClientError> {
+        match (&self.permanent_redirects, response) {
+            (Some(store), Response::PermanentRedirect { .. }) if !sensitive => store.lock().unwrap()
+                .insert(from, to)
+                .map_err(ClientError::FailedToSavePermanentRedirects),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{middleware::Middleware, ClientBuilder, RedirectPolicy};
+    use std::sync::Mutex;
+
+    fn url(url: &str) -> URL {
+        URL::try_from(url).unwrap()
+    }
+
+    #[test]
+    fn redirects_are_followed_to_the_end() {
+        let mut store = PermanentRedirects::in_memory();
+        store.insert(&url("gemini://example.com/a"), &url("gemini://example.com/b")).unwrap();
+        store.insert(&url("gemini://example.com/b"), &url("gemini://example.org/c")).unwrap();
+
+        assert_eq!(store.resolve(&url("gemini://example.com/a")), [url("gemini://example.com/b"), url("gemini://example.org/c")]);
+        assert_eq!(store.resolve(&url("gemini://example.org/c")), []);
+
+        store.insert(&url("gemini://example.org/c"), &url("gemini://example.com/a")).unwrap();
+        assert_eq!(store.resolve(&url("gemini://example.com/a")), []);
+    }
+
+    #[test]
+    fn store_round_trips_through_its_file() {
+        let path = std::env::temp_dir().join(format!("yagc_redirects_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = PermanentRedirects::new(path.clone()).unwrap();
+        store.insert(&url("gemini://example.com/old"), &url("gemini://example.com/new")).unwrap();
+
+        let mut store = PermanentRedirects::new(path.clone()).unwrap();
+        assert_eq!(store.resolve(&url("gemini://example.com/old")), [url("gemini://example.com/new")]);
+
+        store.remove(&url("gemini://example.com/old")).unwrap();
+        let store = PermanentRedirects::new(path.clone()).unwrap();
+        assert_eq!(store.resolve(&url("gemini://example.com/old")), []);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Answers requests itself, permanently redirecting `/old` and answered `/login` prompts to `/new`, and records the URLs requested.
+    struct Moved {
+        requested: Mutex<Vec<String>>,
+    }
+
+    impl Middleware for Moved {
+        fn on_request(&self, url: &mut URL) -> Option<Response> {
+            self.requested.lock().unwrap().push(url.path.clone());
+
+            Some(match (url.path.as_str(), &url.query) {
+                ("/old", _) | ("/login", Some(_)) => Response::PermanentRedirect { url: "gemini://example.com/new".to_string() },
+                ("/login", None) => Response::SensitiveInput { prompt: "Password".to_string() },
+                _ => Response::NotFound { information: "meow".to_string() },
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn remembered_redirects_are_requested_directly() {
+        let moved = std::sync::Arc::new(Moved { requested: Mutex::new(Vec::new()) });
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .remember_permanent_redirects()
+            .middleware(moved.clone())
+            .build()
+            .unwrap();
+
+        client.fetch(&url("gemini://example.com/old")).await.unwrap();
+        client.fetch(&url("gemini://example.com/old")).await.unwrap();
+        assert_eq!(*moved.requested.lock().unwrap(), ["/old", "/new", "/new"]);
+        assert_eq!(client.permanent_redirects(), [(url("gemini://example.com/old"), url("gemini://example.com/new"))]);
+
+        client.clear_permanent_redirects().unwrap();
+        client.fetch(&url("gemini://example.com/old")).await.unwrap();
+        assert_eq!(moved.requested.lock().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn chain_starts_with_the_requested_url() {
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .remember_permanent_redirects()
+            .middleware(Moved { requested: Mutex::new(Vec::new()) })
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let result = client.fetch_with_chain(&url("gemini://example.com/old")).await.unwrap();
+            assert_eq!(result.chain, [url("gemini://example.com/old")]);
+            assert_eq!(result.url, url("gemini://example.com/new"));
+        }
+    }
+
+    #[tokio::test]
+    async fn redirects_of_sensitive_input_are_not_remembered() {
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .remember_permanent_redirects()
+            .middleware(Moved { requested: Mutex::new(Vec::new()) })
+            .build()
+            .unwrap();
+
+        client.fetch_with_answer(&url("gemini://example.com/login"), "hunter2").await.unwrap();
+        assert_eq!(client.permanent_redirects(), []);
+    }
+
+    #[tokio::test]
+    async fn remembered_redirects_follow_the_redirect_policy() {
+        let path = std::env::temp_dir().join(format!("yagc_redirects_policy_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = PermanentRedirects::new(path.clone()).unwrap();
+        store.insert(&url("gemini://example.com/old"), &url("gemini://example.org/new")).unwrap();
+
+        /// Answers every request itself and records the redirects it is told about.
+        struct Redirects(Mutex<Vec<String>>);
+
+        impl Middleware for Redirects {
+            fn on_request(&self, _url: &mut URL) -> Option<Response> {
+                Some(Response::NotFound { information: "meow".to_string() })
+            }
+
+            fn on_redirect(&self, from: &URL, to: &URL) {
+                self.0.lock().unwrap().push(format!("{from} {to}"));
+            }
+        }
+
+        let redirects = std::sync::Arc::new(Redirects(Mutex::new(Vec::new())));
+        let client = |allow_cross_host| ClientBuilder::new()
+            .in_memory_tofu_store()
+            .permanent_redirect_store_path(path.clone())
+            .redirect_policy(RedirectPolicy { allow_cross_host, ..RedirectPolicy::default() })
+            .middleware(redirects.clone())
+            .build()
+            .unwrap();
+
+        let result = client(false).fetch(&url("gemini://example.com/old")).await;
+        assert!(matches!(result, Err(ClientError::CrossHostRedirect(_))));
+        assert_eq!(*redirects.0.lock().unwrap(), Vec::<String>::new());
+
+        client(true).fetch(&url("gemini://example.com/old")).await.unwrap();
+        assert_eq!(*redirects.0.lock().unwrap(), ["gemini://example.com:1965/old gemini://example.org:1965/new"]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn downloads_follow_remembered_redirects_like_fetch() {
+        use crate::client::identity::tests::{capsule, serve};
+
+        let path = std::env::temp_dir().join(format!("yagc_redirects_download_test_{}.json", std::process::id()));
+        let download_path = std::env::temp_dir().join(format!("yagc_redirects_download_test_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (port, requests) = serve(&["20 text/plain\r\nmeow"]).await;
+        let mut store = PermanentRedirects::new(path.clone()).unwrap();
+        store.insert(&url(&format!("gemini://capsule.test:{port}/old")), &url(&format!("gemini://capsule.test:{port}/new"))).unwrap();
+        store.insert(&url("gemini://example.com/old"), &url("gemini://example.org/new")).unwrap();
+
+        /// Records the redirects it is told about.
+        struct Redirects(Mutex<Vec<String>>);
+
+        impl Middleware for Redirects {
+            fn on_redirect(&self, from: &URL, to: &URL) {
+                self.0.lock().unwrap().push(format!("{from} {to}"));
+            }
+        }
+
+        let redirects = std::sync::Arc::new(Redirects(Mutex::new(Vec::new())));
+        let client = capsule()
+            .permanent_redirect_store_path(path.clone())
+            .redirect_policy(RedirectPolicy { allow_cross_host: false, ..RedirectPolicy::default() })
+            .middleware(redirects.clone())
+            .build()
+            .unwrap();
+
+        let result = client.download(&url("gemini://example.com/old"), &download_path, |_| {}).await;
+        assert!(matches!(result, Err(ClientError::CrossHostRedirect(_))));
+
+        client.download(&url(&format!("gemini://capsule.test:{port}/old")), &download_path, |_| {}).await.unwrap();
+        assert_eq!(*redirects.0.lock().unwrap(), [format!("gemini://capsule.test:{port}/old gemini://capsule.test:{port}/new")]);
+        assert_eq!(*requests.lock().unwrap(), [format!("gemini://capsule.test:{port}/new")]);
+        assert_eq!(std::fs::read_to_string(&download_path).unwrap(), "meow");
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&download_path);
+    }
+}
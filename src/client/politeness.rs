@@ -0,0 +1,268 @@
+use crate::url::Host;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+const DEFAULT_MIN_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// A policy limiting how hard the client hits hosts, for crawlers and other bulk workloads.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PolitenessPolicy {
+    /// The minimum time between the starts of two requests to the same host.
+    pub min_delay: Duration,
+    /// The maximum number of requests in flight at once, across all hosts.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for PolitenessPolicy {
+    fn default() -> Self {
+        Self {
+            min_delay: DEFAULT_MIN_DELAY,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+/// How urgently a request should be sent when the politeness policy's concurrency limit is reached.
+/// Waiting requests start in order of priority, and in the order they were made within a priority.
+/// Requests have `Normal` priority unless they are made within `Priority::scope`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+pub enum Priority {
+    /// Prefetching, crawling and other work nobody is waiting on.
+    Background,
+    /// Requests made without a priority.
+    #[default]
+    Normal,
+    /// Requests for what the user is looking at, such as the foreground tab.
+    Foreground,
+}
+
+impl Priority {
+    /// Run a future, making every request it makes through a `Client` with this priority.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        PRIORITY.scope(self, future).await
+    }
+
+    /// Get the priority of requests made by the current task.
+    fn current() -> Self {
+        PRIORITY.try_with(|priority| *priority).unwrap_or_default()
+    }
+}
+
+/// A request waiting for a permit.
+#[derive(Debug)]
+struct Waiter {
+    priority: Priority,
+    sequence: u64,
+    sender: oneshot::Sender<()>,
+}
+
+impl Waiter {
+    fn key(&self) -> (Priority, Reverse<u64>) {
+        (self.priority, Reverse(self.sequence))
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The permits not in use, and the requests waiting for one.
+#[derive(Debug)]
+struct Permits {
+    available: usize,
+    waiting: BinaryHeap<Waiter>,
+    next_sequence: u64,
+}
+
+/// Allows a request to count towards the concurrency limit until it is dropped.
+#[derive(Debug)]
+pub struct Permit<'a> {
+    scheduler: &'a Scheduler,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// A request's place in the queue, which gives back its permit if it is dropped after being handed one.
+struct Waiting<'a> {
+    scheduler: &'a Scheduler,
+    receiver: oneshot::Receiver<()>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.receiver.try_recv().is_ok() {
+            self.scheduler.release();
+        }
+    }
+}
+
+/// Schedules requests according to a `PolitenessPolicy`.
+#[derive(Debug)]
+pub struct Scheduler {
+    min_delay: Duration,
+    permits: Mutex<Permits>,
+    next_start: Mutex<HashMap<Host, Instant>>,
+}
+
+impl Scheduler {
+    /// Create a new scheduler following the given policy.
+    pub fn new(policy: &PolitenessPolicy) -> Self {
+        Self {
+            min_delay: policy.min_delay,
+            permits: Mutex::new(Permits {
+                available: policy.max_concurrent_requests.max(1),
+                waiting: BinaryHeap::new(),
+                next_sequence: 0,
+            }),
+            next_start: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until a request to the host may start, taking the current task's `Priority` into account.
+    /// The request counts towards the concurrency limit until the permit is dropped.
+    pub async fn acquire(&self, host: &Host) -> Permit<'_> {
+        if let Some(receiver) = self.enqueue(Priority::current()) {
+            let mut waiting = Waiting { scheduler: self, receiver };
+            // waiters are only removed from the queue by sending to them
+            (&mut waiting.receiver).await.unwrap();
+        }
+        let permit = Permit { scheduler: self };
+
+        let start = self.reserve(host, Instant::now());
+        tokio::time::sleep_until(start.into()).await;
+
+        permit
+    }
+
+    /// Take a permit if one is free and nobody is waiting, or join the queue for one otherwise.
+    fn enqueue(&self, priority: Priority) -> Option<oneshot::Receiver<()>> {
+        let mut permits = self.permits.lock().unwrap();
+
+        if permits.available > 0 && permits.waiting.is_empty() {
+            permits.available -= 1;
+            return None;
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let sequence = permits.next_sequence;
+        permits.next_sequence += 1;
+        permits.waiting.push(Waiter { priority, sequence, sender });
+
+        Some(receiver)
+    }
+
+    /// Hand a permit to the most urgent request still waiting, or make it available if there are none.
+    fn release(&self) {
+        let mut permits = self.permits.lock().unwrap();
+
+        while let Some(waiter) = permits.waiting.pop() {
+            // fails if the request stopped waiting
+            if waiter.sender.send(()).is_ok() {
+                return;
+            }
+        }
+
+        permits.available += 1;
+    }
+
+    /// Reserve the earliest start time for a request to the host that respects the minimum delay.
+    fn reserve(&self, host: &Host, now: Instant) -> Instant {
+        let mut next_start = self.next_start.lock().unwrap();
+
+        let start = next_start.get(host).map_or(now, |next| now.max(*next));
+        next_start.insert(host.clone(), start + self.min_delay);
+
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn host(name: &str) -> Host {
+        Host { name: name.to_string(), port: 1965 }
+    }
+
+    #[test]
+    fn requests_to_one_host_are_spaced_out() {
+        let scheduler = Scheduler::new(&PolitenessPolicy { min_delay: Duration::from_secs(2), ..Default::default() });
+        let now = Instant::now();
+
+        assert_eq!(scheduler.reserve(&host("example.com"), now), now);
+        assert_eq!(scheduler.reserve(&host("example.com"), now), now + Duration::from_secs(2));
+        assert_eq!(scheduler.reserve(&host("example.com"), now + Duration::from_secs(1)), now + Duration::from_secs(4));
+        assert_eq!(scheduler.reserve(&host("example.com"), now + Duration::from_secs(10)), now + Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn urgent_requests_go_first() {
+        let scheduler = Arc::new(Scheduler::new(&PolitenessPolicy { min_delay: Duration::ZERO, max_concurrent_requests: 1 }));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = scheduler.acquire(&host("example.com")).await;
+
+        let tasks: Vec<_> = [Priority::Background, Priority::Normal, Priority::Foreground, Priority::Background]
+            .into_iter()
+            .enumerate()
+            .map(|(index, priority)| {
+                let scheduler = scheduler.clone();
+                let order = order.clone();
+
+                tokio::spawn(priority.scope(async move {
+                    let _permit = scheduler.acquire(&host("example.com")).await;
+                    order.lock().unwrap().push(index);
+                }))
+            })
+            .collect();
+        // let every task join the queue
+        tokio::task::yield_now().await;
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), [2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn hosts_are_scheduled_independently() {
+        let scheduler = Scheduler::new(&PolitenessPolicy::default());
+        let now = Instant::now();
+
+        assert_eq!(scheduler.reserve(&host("example.com"), now), now);
+        assert_eq!(scheduler.reserve(&host("example.org"), now), now);
+    }
+}
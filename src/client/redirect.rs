@@ -0,0 +1,136 @@
+use super::ClientError;
+use crate::{response::Response, url::URL};
+
+const DEFAULT_MAX_REDIRECTS: usize = 5;
+
+/// A policy controlling which redirects `Client::fetch` follows.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RedirectPolicy {
+    /// The maximum number of redirects to follow for a single fetch.
+    pub max_redirects: usize,
+    /// Whether redirects to a different host are followed.
+    pub allow_cross_host: bool,
+    /// Whether to fail when a redirect leads back to an already visited URL.
+    pub detect_loops: bool,
+}
+
+/// The outcome of a fetch that may have followed redirects, from `Client::fetch_with_chain`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FetchResult {
+    /// The final response.
+    pub response: Response,
+    /// The URL the final response came from, which relative links in it should be resolved against.
+    pub url: URL,
+    /// The URLs that redirected, in the order they were requested, starting with the URL fetched. Empty if there were no redirects.
+    pub chain: Vec<URL>,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            allow_cross_host: true,
+            detect_loops: true,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// Check whether a redirect to `target` may be followed, given the URLs visited so far (starting with the original URL).
+    pub fn check(&self, target: &URL, visited: &[URL]) -> Result<(), ClientError> {
+        if visited.len() > self.max_redirects {
+            return Err(ClientError::TooManyRedirects(format!("Stopped after {} redirects at {target}", self.max_redirects)));
+        }
+
+        let current_host = visited.last().and_then(|url| url.host.as_ref());
+        let is_same_host = match (current_host, &target.host) {
+            (Some(current), Some(target)) => current.name.eq_ignore_ascii_case(&target.name),
+            (None, None) => true,
+            _ => false,
+        };
+        if !self.allow_cross_host && !is_same_host {
+            return Err(ClientError::CrossHostRedirect(target.to_string()));
+        }
+
+        if self.detect_loops && visited.contains(target) {
+            return Err(ClientError::RedirectLoop(target.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{middleware::Middleware, ClientBuilder};
+
+    fn url(url: &str) -> URL {
+        URL::try_from(url).unwrap()
+    }
+
+    #[test]
+    fn allowed_redirect() {
+        let policy = RedirectPolicy::default();
+        let result = policy.check(&url("gemini://example.com/b"), &[url("gemini://example.com/a")]);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn too_many_redirects() {
+        let policy = RedirectPolicy { max_redirects: 1, ..Default::default() };
+        let visited = [url("gemini://example.com/a"), url("gemini://example.com/b")];
+        let result = policy.check(&url("gemini://example.com/c"), &visited);
+
+        assert!(matches!(result, Err(ClientError::TooManyRedirects(_))));
+    }
+
+    #[test]
+    fn cross_host_redirect() {
+        let policy = RedirectPolicy { allow_cross_host: false, ..Default::default() };
+        let visited = [url("gemini://example.com/a")];
+
+        assert!(policy.check(&url("gemini://EXAMPLE.com/b"), &visited).is_ok());
+        assert!(matches!(policy.check(&url("gemini://example.org/b"), &visited), Err(ClientError::CrossHostRedirect(_))));
+    }
+
+    #[test]
+    fn redirect_loop() {
+        let policy = RedirectPolicy::default();
+        let visited = [url("gemini://example.com/a"), url("gemini://example.com/b")];
+        let result = policy.check(&url("gemini://example.com/a"), &visited);
+
+        assert!(matches!(result, Err(ClientError::RedirectLoop(_))));
+    }
+
+    /// Answers requests itself, redirecting `/a` to `/b` to `/c`.
+    struct Redirects;
+
+    impl Middleware for Redirects {
+        fn on_request(&self, url: &mut URL) -> Option<Response> {
+            Some(match url.path.as_str() {
+                "/a" => Response::TemporaryRedirect { url: "gemini://example.com/b".to_string() },
+                "/b" => Response::PermanentRedirect { url: "gemini://example.com/c".to_string() },
+                _ => Response::NotFound { information: "meow".to_string() },
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_returns_the_chain() {
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .middleware(Redirects)
+            .build()
+            .unwrap();
+
+        let result = client.fetch_with_chain(&url("gemini://example.com/a")).await.unwrap();
+        assert_eq!(result.response.status_code(), 51);
+        assert_eq!(result.url, url("gemini://example.com/c"));
+        assert_eq!(result.chain, [url("gemini://example.com/a"), url("gemini://example.com/b")]);
+
+        let result = client.fetch_with_chain(&url("gemini://example.com/c")).await.unwrap();
+        assert!(result.chain.is_empty());
+    }
+}
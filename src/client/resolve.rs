@@ -0,0 +1,164 @@
+use super::Client;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::net::lookup_host;
+
+/// The future returned by `Resolver::resolve`.
+pub type Resolution<'a> = Pin<Box<dyn Future<Output = Result<Vec<IpAddr>, String>> + Send + 'a>>;
+
+/// Resolves hostnames to the addresses the client connects to.
+/// Implement it to use another resolver, such as DNS over HTTPS, and set it with `ClientBuilder::resolver`.
+pub trait Resolver: Send + Sync {
+    /// Get the addresses of a hostname, in the order they should be tried.
+    fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a>;
+}
+
+// so a resolver can be shared with the code that set it
+impl<T: Resolver + ?Sized> Resolver for Arc<T> {
+    fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a> {
+        (**self).resolve(hostname)
+    }
+}
+
+/// Resolves hostnames with the operating system's resolver. This is the client's default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a> {
+        Box::pin(async move {
+            let addresses = lookup_host((hostname, 0)).await.map_err(|e| e.to_string())?;
+
+            Ok(addresses.map(|address| address.ip()).collect())
+        })
+    }
+}
+
+/// Resolves hostnames from a fixed map, like a hosts file. Useful for testing.
+#[derive(Debug, Default, Clone)]
+pub struct StaticResolver {
+    hosts: HashMap<String, Vec<IpAddr>>, // hostname -> addresses
+}
+
+impl StaticResolver {
+    /// Create a resolver that knows no hosts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve `hostname` to the given addresses.
+    pub fn host(mut self, hostname: &str, addresses: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.hosts.insert(hostname.to_string(), addresses.into_iter().collect());
+        self
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a> {
+        let addresses = self.hosts.get(hostname)
+            .cloned()
+            .ok_or_else(|| format!("Unknown host {hostname}"));
+
+        Box::pin(std::future::ready(addresses))
+    }
+}
+
+/// Caches the addresses found by another resolver for a fixed time. Failed lookups are not cached.
+#[derive(Debug)]
+pub struct CachingResolver<R> {
+    inner: R,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>, // hostname -> (addresses, time resolved)
+}
+
+impl<R: Resolver> CachingResolver<R> {
+    /// Cache the addresses found by `inner` for `ttl`.
+    pub fn new(inner: R, ttl: Duration) -> Self {
+        Self { inner, ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Forget every cached address.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn cached(&self, hostname: &str, now: Instant) -> Option<Vec<IpAddr>> {
+        let entries = self.entries.lock().unwrap();
+        let (addresses, resolved_at) = entries.get(hostname)?;
+
+        (now.saturating_duration_since(*resolved_at) < self.ttl).then(|| addresses.clone())
+    }
+}
+
+impl<R: Resolver> Resolver for CachingResolver<R> {
+    fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a> {
+        Box::pin(async move {
+            if let Some(addresses) = self.cached(hostname, Instant::now()) {
+                return Ok(addresses);
+            }
+
+            let addresses = self.inner.resolve(hostname).await?;
+            self.entries.lock().unwrap().insert(hostname.to_string(), (addresses.clone(), Instant::now()));
+
+            Ok(addresses)
+        })
+    }
+}
+
+impl Client {
+    /// Get the socket addresses of a host with the client's resolver. IP addresses are used as they are.
+    pub(super) async fn resolve(&self, hostname: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+        let addresses = match hostname.parse::<IpAddr>() {
+            Ok(address) => vec![address],
+            Err(_) => self.resolver.resolve(hostname).await?,
+        };
+
+        Ok(addresses.into_iter().map(|address| SocketAddr::new(address, port)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::Ipv4Addr, sync::atomic::{AtomicUsize, Ordering}};
+
+    /// Counts the lookups that reach it.
+    struct Counting {
+        inner: StaticResolver,
+        lookups: AtomicUsize,
+    }
+
+    impl Resolver for Counting {
+        fn resolve<'a>(&'a self, hostname: &'a str) -> Resolution<'a> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            self.inner.resolve(hostname)
+        }
+    }
+
+    #[tokio::test]
+    async fn lookups_are_cached_until_they_expire() {
+        let address = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let counting = Arc::new(Counting {
+            inner: StaticResolver::new().host("example.com", [address]),
+            lookups: AtomicUsize::new(0),
+        });
+        let resolver = CachingResolver::new(counting.clone(), Duration::from_secs(60));
+
+        assert_eq!(resolver.resolve("example.com").await, Ok(vec![address]));
+        assert_eq!(resolver.resolve("example.com").await, Ok(vec![address]));
+        assert!(resolver.resolve("example.org").await.is_err());
+        assert!(resolver.resolve("example.org").await.is_err());
+        assert_eq!(counting.lookups.load(Ordering::Relaxed), 3);
+
+        assert_eq!(resolver.cached("example.com", Instant::now() + Duration::from_secs(60)), None);
+        resolver.clear();
+        resolver.resolve("example.com").await.unwrap();
+        assert_eq!(counting.lookups.load(Ordering::Relaxed), 4);
+    }
+}
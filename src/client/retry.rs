@@ -0,0 +1,182 @@
+use super::ClientError;
+use crate::{response::Response, url::URL};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A policy controlling how failed requests are retried.
+/// Requests are retried after connection failures and `TemporaryFailure`/`ServerUnavailable` responses.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is retried. By default, requests are not retried.
+    pub max_retries: usize,
+    /// The delay before the first retry. Each following retry waits twice as long as the one before.
+    pub initial_backoff: Duration,
+    /// The longest delay between two attempts.
+    pub max_backoff: Duration,
+    /// Whether to randomise each delay (between half and all of it), so many clients don't retry in lockstep.
+    pub jitter: bool,
+    /// Whether to retry requests carrying sensitive input, such as passwords. Off by default, so credentials aren't submitted twice.
+    pub retry_sensitive_input: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            jitter: true,
+            retry_sensitive_input: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Check whether the result of an attempt is worth retrying.
+    pub fn should_retry(&self, result: &Result<Response, ClientError>) -> bool {
+        match result {
+            Ok(Response::TemporaryFailure { .. } | Response::ServerUnavailable { .. }) => true,
+            Ok(_) => false,
+            Err(e) => matches!(
+                e,
+                ClientError::FailedToResolveHostAddress(_)
+                    | ClientError::FailedToConnectToHost(_)
+                    | ClientError::FailedToReadResponse(_)
+                    | ClientError::ConnectTimeout(_)
+                    | ClientError::HandshakeTimeout(_)
+                    | ClientError::ReadTimeout(_)
+                    | ClientError::RequestWriteFailed { .. }
+            ),
+        }
+    }
+
+    /// Get the delay before the given retry, counting from 1.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1).min(u32::MAX as usize) as u32;
+        let backoff = self.initial_backoff
+            .checked_mul(2u32.saturating_pow(exponent))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if self.jitter {
+            // `RandomState` is seeded randomly, which is plenty for spreading retries out
+            let random = RandomState::new().build_hasher().finish();
+            let half = backoff / 2;
+
+            half + half.mul_f64(random as f64 / u64::MAX as f64)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Information about a retry that is about to happen, passed to the client's retry hook.
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// The URL being requested.
+    pub url: &'a URL,
+    /// Which retry this is, counting from 1.
+    pub retry: usize,
+    /// How long the client waits before retrying.
+    pub delay: Duration,
+    /// The result of the attempt that failed.
+    pub cause: &'a Result<Response, ClientError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{middleware::Middleware, resolve::StaticResolver, ClientBuilder};
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::{atomic::{AtomicUsize, Ordering}, Arc},
+    };
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn retryable_results() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.should_retry(&Ok(Response::ServerUnavailable { information: "meow".to_string() })));
+        assert!(policy.should_retry(&Err(ClientError::ConnectTimeout("example.com".to_string()))));
+        assert!(!policy.should_retry(&Ok(Response::NotFound { information: "meow".to_string() })));
+        assert!(!policy.should_retry(&Err(ClientError::Cancelled("gemini://example.com/".to_string()))));
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            jitter: false,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn jittered_backoff() {
+        let policy = RetryPolicy { initial_backoff: Duration::from_secs(2), ..Default::default() };
+
+        for _ in 0..100 {
+            let backoff = policy.backoff(1);
+            assert!(backoff >= Duration::from_secs(1) && backoff <= Duration::from_secs(2));
+        }
+    }
+
+    /// Asks for a password, leaving answered requests to go to the host.
+    struct Login;
+
+    impl Middleware for Login {
+        fn on_request(&self, url: &mut URL) -> Option<Response> {
+            match url.query {
+                Some(_) => None,
+                None => Some(Response::SensitiveInput { prompt: "Password".to_string() }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn sensitive_input_is_sent_once() {
+        for (retry_sensitive_input, attempts) in [(false, 1), (true, 3)] {
+            // a host that hangs up on every connection, which is worth retrying
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let connections = Arc::new(AtomicUsize::new(0));
+            let server = tokio::spawn({
+                let connections = connections.clone();
+                async move {
+                    loop {
+                        let (stream, _) = listener.accept().await.unwrap();
+                        connections.fetch_add(1, Ordering::SeqCst);
+                        drop(stream);
+                    }
+                }
+            });
+
+            let client = ClientBuilder::new()
+                .in_memory_tofu_store()
+                .resolver(StaticResolver::new().host("login.test", [IpAddr::V4(Ipv4Addr::LOCALHOST)]))
+                .retry_policy(RetryPolicy { max_retries: 2, initial_backoff: Duration::from_millis(1), retry_sensitive_input, ..Default::default() })
+                .middleware(Login)
+                .build()
+                .unwrap();
+            let url = URL::try_from(format!("gemini://login.test:{port}/login").as_str()).unwrap();
+
+            assert!(client.fetch_with_answer(&url, "hunter2").await.is_err());
+            assert_eq!(connections.load(Ordering::SeqCst), attempts);
+            server.abort();
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::url::Host;
+use std::net::IpAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const VERSION: u8 = 0x05;
+const NO_AUTHENTICATION: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+const CONNECT: u8 = 0x01;
+const IPV4: u8 = 0x01;
+const DOMAIN_NAME: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy to open TCP connections through, such as Tor's SOCKS port.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Socks5Proxy {
+    /// The proxy's host.
+    pub host: Host,
+    /// The username and password to authenticate with, if the proxy needs them.
+    pub credentials: Option<(String, String)>,
+    /// Whether to send different credentials for each host, when none are set, so Tor uses a separate circuit for each.
+    pub isolate_hosts: bool,
+}
+
+impl Socks5Proxy {
+    /// Create a proxy at the given host that doesn't need authentication.
+    pub fn new(host: Host) -> Self {
+        Self { host, credentials: None, isolate_hosts: false }
+    }
+
+    /// Authenticate with the proxy using a username and password.
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Use a separate Tor circuit for each host, so the exit relays can't link requests to different hosts.
+    /// This relies on Tor's default `IsolateSOCKSAuth`, and has no effect when credentials are set.
+    pub fn isolate_hosts(mut self) -> Self {
+        self.isolate_hosts = true;
+        self
+    }
+
+    /// Ask the proxy to connect the stream to a host. Hostnames are resolved by the proxy, so lookups don't leak around it.
+    pub(super) async fn connect<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: &mut S, hostname: &str, port: u16) -> Result<(), String> {
+        let isolation;
+        let credentials = match &self.credentials {
+            Some((username, password)) => Some((username.as_str(), password.as_str())),
+            None if self.isolate_hosts => {
+                isolation = format!("{hostname}:{port}");
+                Some((isolation.as_str(), "yagc"))
+            },
+            None => None,
+        };
+        Self::authenticate(stream, credentials).await?;
+
+        let mut request = vec![VERSION, CONNECT, 0x00];
+        match hostname.parse::<IpAddr>() {
+            Ok(IpAddr::V4(address)) => {
+                request.push(IPV4);
+                request.extend_from_slice(&address.octets());
+            },
+            Ok(IpAddr::V6(address)) => {
+                request.push(IPV6);
+                request.extend_from_slice(&address.octets());
+            },
+            Err(_) => {
+                let length = u8::try_from(hostname.len()).map_err(|_| format!("Hostname is too long: {hostname}"))?;
+                request.extend_from_slice(&[DOMAIN_NAME, length]);
+                request.extend_from_slice(hostname.as_bytes());
+            },
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await.map_err(|e| e.to_string())?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply).await.map_err(|e| e.to_string())?;
+        if reply[0] != VERSION {
+            return Err(format!("Unexpected SOCKS version {}", reply[0]));
+        }
+        if reply[1] != 0x00 {
+            return Err(format!("The proxy refused to connect to {hostname}:{port}: {}", reply_message(reply[1])));
+        }
+
+        // the address the proxy bound to isn't needed, but must be read past
+        let address_length = match reply[3] {
+            IPV4 => 4,
+            IPV6 => 16,
+            DOMAIN_NAME => stream.read_u8().await.map_err(|e| e.to_string())? as usize,
+            address_type => return Err(format!("Unknown address type {address_type}")),
+        };
+        let mut bound_address = vec![0; address_length + 2];
+        stream.read_exact(&mut bound_address).await.map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Agree on an authentication method with the proxy and authenticate.
+    async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, credentials: Option<(&str, &str)>) -> Result<(), String> {
+        let greeting: &[u8] = match credentials {
+            Some(_) => &[VERSION, 2, NO_AUTHENTICATION, USERNAME_PASSWORD],
+            None => &[VERSION, 1, NO_AUTHENTICATION],
+        };
+        stream.write_all(greeting).await.map_err(|e| e.to_string())?;
+
+        let mut choice = [0; 2];
+        stream.read_exact(&mut choice).await.map_err(|e| e.to_string())?;
+        if choice[0] != VERSION {
+            return Err(format!("Unexpected SOCKS version {}", choice[0]));
+        }
+
+        match (choice[1], credentials) {
+            (NO_AUTHENTICATION, _) => Ok(()),
+            (USERNAME_PASSWORD, Some((username, password))) => {
+                let username_length = u8::try_from(username.len()).map_err(|_| "Username is too long".to_string())?;
+                let password_length = u8::try_from(password.len()).map_err(|_| "Password is too long".to_string())?;
+
+                let mut request = vec![0x01, username_length];
+                request.extend_from_slice(username.as_bytes());
+                request.push(password_length);
+                request.extend_from_slice(password.as_bytes());
+                stream.write_all(&request).await.map_err(|e| e.to_string())?;
+
+                let mut status = [0; 2];
+                stream.read_exact(&mut status).await.map_err(|e| e.to_string())?;
+                match status[1] {
+                    0x00 => Ok(()),
+                    _ => Err("The proxy rejected the username and password".to_string()),
+                }
+            },
+            (NO_ACCEPTABLE_METHODS, _) => Err("The proxy accepts none of the offered authentication methods".to_string()),
+            (method, _) => Err(format!("The proxy chose an authentication method that wasn't offered: {method}")),
+        }
+    }
+}
+
+/// Describe a SOCKS5 reply code.
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy() -> Socks5Proxy {
+        Socks5Proxy::new(Host { name: "localhost".to_string(), port: 9050 })
+    }
+
+    #[tokio::test]
+    async fn connects_with_credentials() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let proxy = proxy().credentials("user", "hunter2");
+
+        let server = tokio::spawn(async move {
+            let mut greeting = [0; 4];
+            server.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 2, 0, 2]);
+            server.write_all(&[5, 2]).await.unwrap();
+
+            let mut authentication = [0; 14];
+            server.read_exact(&mut authentication).await.unwrap();
+            assert_eq!(authentication, *b"\x01\x04user\x07hunter2");
+            server.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0; 18];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, *b"\x05\x01\x00\x03\x0bexample.com\x07\xad");
+            server.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x1f, 0x90]).await.unwrap();
+        });
+
+        proxy.connect(&mut client, "example.com", 1965).await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hosts_are_isolated_by_credentials() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = tokio::spawn(async move {
+            let mut greeting = [0; 4];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[5, 2]).await.unwrap();
+
+            let mut authentication = [0; 23];
+            server.read_exact(&mut authentication).await.unwrap();
+            assert_eq!(authentication, *b"\x01\x10example.com:1965\x04yagc");
+            server.write_all(&[1, 0]).await.unwrap();
+
+            let mut request = [0; 18];
+            server.read_exact(&mut request).await.unwrap();
+            server.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x1f, 0x90]).await.unwrap();
+        });
+
+        proxy().isolate_hosts().connect(&mut client, "example.com", 1965).await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refused_connection_is_an_error() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        let server = tokio::spawn(async move {
+            let mut greeting = [0; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[5, 0]).await.unwrap();
+
+            let mut request = [0; 10];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[3..8], [1, 10, 0, 0, 1]);
+            server.write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        });
+
+        let result = proxy().connect(&mut client, "10.0.0.1", 1965).await;
+        assert!(result.unwrap_err().contains("connection refused"));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use super::{Client, ClientError};
+use crate::{response::{Response, MAX_HEADER_LENGTH}, url::URL};
+
+/// A way a response deviates from the Gemini specification, reported in strict mode.
+/// Responses the parser can't make sense of, such as a body after a non-success header, are rejected in any mode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SpecViolation {
+    /// The header line is longer than the 1024 bytes the spec allows. Only possible if the client's maximum header length was raised.
+    HeaderTooLong {
+        /// The length of the header line, not counting the CRLF.
+        length: usize,
+    },
+    /// A text body in UTF-8, whether declared or by default, isn't valid UTF-8.
+    InvalidTextEncoding {
+        /// The byte offset of the first invalid sequence in the body.
+        offset: usize,
+    },
+    /// A redirect's target isn't a valid URL.
+    InvalidRedirectTarget(String),
+}
+
+/// Find the ways a response to a request for `url` deviates from the spec. `raw` is the response as received.
+fn spec_violations(url: &URL, raw: &[u8], response: &Response) -> Vec<SpecViolation> {
+    let mut violations = Vec::new();
+
+    if let Some(length) = raw.windows(2).position(|window| window == b"\r\n")
+        && length > MAX_HEADER_LENGTH
+    {
+        violations.push(SpecViolation::HeaderTooLong { length });
+    }
+
+    match response {
+        Response::Success { body_mime_type, body }
+            if body_mime_type.mime_type_type.to_ascii_lowercase().starts_with("text/") && body_mime_type.charset.eq_ignore_ascii_case("utf-8") =>
+        {
+            if let Err(e) = std::str::from_utf8(body) {
+                violations.push(SpecViolation::InvalidTextEncoding { offset: e.valid_up_to() });
+            }
+        },
+        Response::TemporaryRedirect { url: target } | Response::PermanentRedirect { url: target }
+            if target.is_empty() || url.join(target).is_err() =>
+        {
+            violations.push(SpecViolation::InvalidRedirectTarget(target.clone()));
+        },
+        _ => (),
+    }
+
+    violations
+}
+
+impl Client {
+    /// Reject a response that deviates from the spec, if the client is in strict mode.
+    pub(super) fn check_spec(&self, url: &URL, raw: &[u8], response: &Response) -> Result<(), ClientError> {
+        if !self.strict {
+            return Ok(());
+        }
+
+        let violations = spec_violations(url, raw, response);
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(ClientError::SpecViolations(violations)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(raw: &[u8]) -> Vec<SpecViolation> {
+        let response = Response::from_bytes_with_limit(raw, 2048).unwrap();
+
+        spec_violations(&URL::try_from("gemini://example.com/").unwrap(), raw, &response)
+    }
+
+    #[test]
+    fn deviations_from_the_spec_are_reported() {
+        assert_eq!(violations(b"20 text/gemini\r\n# Hello"), []);
+        assert_eq!(violations(b"20 text/plain;charset=iso-8859-1\r\ncaf\xe9"), []);
+        assert_eq!(violations(b"20 text/plain\r\ncaf\xe9"), [SpecViolation::InvalidTextEncoding { offset: 3 }]);
+        assert_eq!(violations(b"20 text/plain; charset=utf-8\r\ncaf\xe9"), [SpecViolation::InvalidTextEncoding { offset: 3 }]);
+        assert_eq!(violations(b"20 image/png\r\n\x89PNG"), []);
+
+        assert_eq!(violations(b"31 /elsewhere\r\n"), []);
+        assert_eq!(violations(b"31 \r\n"), [SpecViolation::InvalidRedirectTarget(String::new())]);
+
+        let long = format!("51 {}\r\n", "a".repeat(1100));
+        assert_eq!(violations(long.as_bytes()), [SpecViolation::HeaderTooLong { length: long.len() - 2 }]);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::url::Host;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How often the measured throughput is updated.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits on how fast the client reads responses, in bytes per second, so bulk work doesn't saturate the connection.
+/// By default, reads are not limited.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BandwidthPolicy {
+    /// The limit across all hosts.
+    pub global: Option<u64>,
+    /// The limit for each host on its own.
+    pub per_host: Option<u64>,
+}
+
+/// A token bucket holding up to one second's worth of bytes.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        let rate = rate.max(1) as f64;
+
+        Self { rate, tokens: rate, last_refill: now }
+    }
+
+    /// Take tokens for bytes that were read, returning how long to wait before reading more.
+    fn take(&mut self, bytes: usize, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate) - bytes as f64;
+        self.last_refill = self.last_refill.max(now);
+
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Bytes read in the current window, and the rate measured over the last one.
+#[derive(Debug)]
+struct Throughput {
+    window_start: Instant,
+    bytes: u64,
+    bytes_per_second: f64,
+}
+
+/// Applies a `BandwidthPolicy` to reads and measures the client's throughput.
+#[derive(Debug)]
+pub struct Throttle {
+    policy: BandwidthPolicy,
+    global: Mutex<Option<TokenBucket>>,
+    hosts: Mutex<HashMap<Host, TokenBucket>>,
+    throughput: Mutex<Throughput>,
+}
+
+impl Throttle {
+    /// Create a new throttle following the given policy.
+    pub fn new(policy: &BandwidthPolicy) -> Self {
+        let now = Instant::now();
+
+        Self {
+            policy: policy.clone(),
+            global: Mutex::new(policy.global.map(|rate| TokenBucket::new(rate, now))),
+            hosts: Mutex::new(HashMap::new()),
+            throughput: Mutex::new(Throughput { window_start: now, bytes: 0, bytes_per_second: 0.0 }),
+        }
+    }
+
+    /// Account for bytes read from a host, waiting as long as the limits require before the next read.
+    pub async fn consume(&self, host: &Host, bytes: usize) {
+        let delay = self.delay(host, bytes, Instant::now());
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Get the client's recent throughput across all hosts, in bytes per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        let throughput = self.throughput.lock().unwrap();
+        let elapsed = throughput.window_start.elapsed();
+
+        // a window that has run long is more recent than the last one measured
+        if elapsed >= 2 * THROUGHPUT_WINDOW {
+            throughput.bytes as f64 / elapsed.as_secs_f64()
+        } else {
+            throughput.bytes_per_second
+        }
+    }
+
+    /// Record bytes read from a host and get the delay needed to stay within the limits.
+    fn delay(&self, host: &Host, bytes: usize, now: Instant) -> Duration {
+        let mut throughput = self.throughput.lock().unwrap();
+        throughput.bytes += bytes as u64;
+        let elapsed = now.saturating_duration_since(throughput.window_start);
+        if elapsed >= THROUGHPUT_WINDOW {
+            throughput.bytes_per_second = throughput.bytes as f64 / elapsed.as_secs_f64();
+            throughput.bytes = 0;
+            throughput.window_start = now;
+        }
+        drop(throughput);
+
+        let global = self.global.lock().unwrap()
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(bytes, now));
+        let per_host = self.policy.per_host.map_or(Duration::ZERO, |rate| {
+            self.hosts.lock().unwrap()
+                .entry(host.clone())
+                .or_insert_with(|| TokenBucket::new(rate, now))
+                .take(bytes, now)
+        });
+
+        global.max(per_host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str) -> Host {
+        Host { name: name.to_string(), port: 1965 }
+    }
+
+    #[test]
+    fn token_bucket_delays_once_empty() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(1000, now);
+
+        assert_eq!(bucket.take(1000, now), Duration::ZERO);
+        assert_eq!(bucket.take(500, now), Duration::from_millis(500));
+        // half a second later the debt is paid off
+        assert_eq!(bucket.take(0, now + Duration::from_millis(500)), Duration::ZERO);
+        // the bucket never holds more than a second's worth
+        assert_eq!(bucket.take(2000, now + Duration::from_secs(10)), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn per_host_limits_are_independent() {
+        let throttle = Throttle::new(&BandwidthPolicy { global: None, per_host: Some(100) });
+        let now = Instant::now();
+
+        assert_eq!(throttle.delay(&host("example.com"), 200, now), Duration::from_secs(1));
+        assert_eq!(throttle.delay(&host("example.org"), 100, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn unlimited_policy_never_delays() {
+        let throttle = Throttle::new(&BandwidthPolicy::default());
+
+        assert_eq!(throttle.delay(&host("example.com"), usize::MAX, Instant::now()), Duration::ZERO);
+    }
+}
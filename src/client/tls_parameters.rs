@@ -0,0 +1,137 @@
+use super::Client;
+use crate::url::Host;
+use rustls::{crypto::CryptoProvider, ClientConnection, ProtocolVersion};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The TLS parameters a handshake with a host settled on.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct TlsParameters {
+    tls_1_3: bool,
+    key_exchange_group: Option<u16>, // IANA named group code
+}
+
+impl TlsParameters {
+    /// Get the parameters negotiated on a connection.
+    fn of(connection: &ClientConnection) -> Self {
+        Self {
+            tls_1_3: connection.protocol_version() == Some(ProtocolVersion::TLSv1_3),
+            key_exchange_group: connection.negotiated_key_exchange_group().map(|group| u16::from(group.name())),
+        }
+    }
+
+    /// Get whether the host spoke TLS 1.3, so TLS 1.2 needn't be offered.
+    pub fn tls_1_3(&self) -> bool {
+        self.tls_1_3
+    }
+
+    /// Get a crypto provider like `provider` that offers the key exchange group the host chose first.
+    /// rustls sends a key share for the first group, so the server doesn't have to ask for another one.
+    pub fn prefer_key_exchange_group(&self, mut provider: CryptoProvider) -> CryptoProvider {
+        if let Some(group) = self.key_exchange_group {
+            provider.kx_groups.sort_by_key(|kx_group| u16::from(kx_group.name()) != group);
+        }
+
+        provider
+    }
+}
+
+/// The TLS parameters each host's last handshake settled on, optionally kept in a file between runs.
+#[derive(Debug, Default)]
+pub struct TlsParametersStore {
+    path: Option<PathBuf>, // none for an in-memory store
+    hosts: BTreeMap<String, TlsParameters>, // host -> parameters
+}
+
+impl TlsParametersStore {
+    /// Create a store saved to the file at `path`, loading the parameters already in it if it exists.
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let hosts = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("Failed to open {}: {e}", path.display())),
+        };
+
+        Ok(Self { path: Some(path), hosts })
+    }
+
+    /// Create an empty store that is never saved to disk.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Remember the parameters a host's handshake settled on.
+    fn insert(&mut self, host: &Host, parameters: TlsParameters) {
+        if self.hosts.insert(host.to_string(), parameters) != Some(parameters) {
+            // the parameters are only a hint, so a failed save is retried after the next change
+            let _ = self.save();
+        }
+    }
+
+    /// Forget a host's parameters.
+    fn remove(&mut self, host: &Host) {
+        if self.hosts.remove(&host.to_string()).is_some() {
+            let _ = self.save();
+        }
+    }
+
+    /// Save the store to its file, if it has one.
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let contents = serde_json::to_string_pretty(&self.hosts).map_err(|e| e.to_string())?;
+        std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+impl Client {
+    /// Get the TLS parameters a host's last handshake settled on, if the client remembers them.
+    pub(super) fn tls_parameters(&self, host: &Host) -> Option<TlsParameters> {
+        self.tls_parameters.as_ref()?.lock().unwrap().hosts.get(&host.to_string()).copied()
+    }
+
+    /// Remember the TLS parameters negotiated with a host, if the client remembers them.
+    pub(super) fn remember_tls_parameters(&self, host: &Host, connection: &ClientConnection) {
+        if let Some(store) = &self.tls_parameters {
+            store.lock().unwrap().insert(host, TlsParameters::of(connection));
+        }
+    }
+
+    /// Forget the TLS parameters of a host whose handshake failed with them, so the next one offers everything again.
+    pub(super) fn forget_tls_parameters(&self, host: &Host) {
+        if let Some(store) = &self.tls_parameters {
+            store.lock().unwrap().remove(host);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::{crypto::aws_lc_rs, NamedGroup};
+
+    #[test]
+    fn store_round_trips_through_its_file() {
+        let path = std::env::temp_dir().join(format!("yagc_tls_parameters_test_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let host = Host { name: "example.com".to_string(), port: 1965 };
+        let parameters = TlsParameters { tls_1_3: true, key_exchange_group: Some(u16::from(NamedGroup::secp384r1)) };
+
+        let mut store = TlsParametersStore::new(path.clone()).unwrap();
+        store.insert(&host, parameters);
+        store.insert(&Host { name: "example.org".to_string(), port: 1965 }, parameters);
+        store.remove(&Host { name: "example.org".to_string(), port: 1965 });
+
+        let store = TlsParametersStore::new(path.clone()).unwrap();
+        assert_eq!(store.hosts.len(), 1);
+        assert_eq!(store.hosts["example.com:1965"], parameters);
+
+        let provider = parameters.prefer_key_exchange_group(aws_lc_rs::default_provider());
+        assert_eq!(provider.kx_groups[0].name(), NamedGroup::secp384r1);
+        assert_eq!(provider.kx_groups.len(), aws_lc_rs::default_provider().kx_groups.len());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
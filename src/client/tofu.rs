@@ -1,8 +1,10 @@
+use super::{authority::AuthorityVerifier, certificate::PeerCertificate};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, io::{BufReader, BufWriter}};
+use sha2::{Digest, Sha256, Sha512};
+use std::{collections::{BTreeMap, HashMap}, fs::File, io::{BufReader, BufWriter, Write}, time::SystemTime};
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
 use rustls::client::danger::{ServerCertVerified, HandshakeSignatureValid};
-use sha2::{Sha256, Digest};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
 
 /// The result of a TOFU verification.
 pub enum TofuResult {
@@ -16,21 +18,103 @@ pub enum TofuResult {
     New,
 }
 
+/// A hash algorithm used to fingerprint certificates.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FingerprintAlgorithm {
+    /// SHA-256, the algorithm fingerprints are usually shared with.
+    #[default]
+    Sha256,
+    /// SHA-512.
+    Sha512,
+}
+
+impl FingerprintAlgorithm {
+    /// Every supported algorithm.
+    pub const ALL: [Self; 2] = [Self::Sha256, Self::Sha512];
+
+    /// Get the algorithm's name, as used in the TOFU store.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Get the hex fingerprint of a DER-encoded certificate.
+    pub fn fingerprint(self, certificate: &[u8]) -> String {
+        match self {
+            Self::Sha256 => hex::encode(Sha256::digest(certificate)),
+            Self::Sha512 => hex::encode(Sha512::digest(certificate)),
+        }
+    }
+}
+
+/// A certificate's fingerprints under one or more algorithms.
+/// Algorithms are keyed by name, so a store with algorithms this version doesn't know still loads, and keeps them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(from = "StoredFingerprints")]
+pub struct Fingerprints(BTreeMap<String, String>); // algorithm name -> hex fingerprint
+
+/// Fingerprints as stored, which were a bare SHA-256 fingerprint before algorithms were recorded.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredFingerprints {
+    Sha256(String),
+    ByAlgorithm(BTreeMap<String, String>),
+}
+
+impl From<StoredFingerprints> for Fingerprints {
+    fn from(stored: StoredFingerprints) -> Self {
+        match stored {
+            StoredFingerprints::Sha256(fingerprint) => Self(BTreeMap::from([(FingerprintAlgorithm::Sha256.name().to_string(), fingerprint)])),
+            StoredFingerprints::ByAlgorithm(fingerprints) => Self(fingerprints),
+        }
+    }
+}
+
+impl Fingerprints {
+    /// Fingerprint a DER-encoded certificate under every supported algorithm.
+    pub fn of(certificate: &[u8]) -> Self {
+        Self(FingerprintAlgorithm::ALL.iter()
+            .map(|algorithm| (algorithm.name().to_string(), algorithm.fingerprint(certificate)))
+            .collect())
+    }
+
+    /// Get the fingerprint under an algorithm, if there is one.
+    pub fn get(&self, algorithm: FingerprintAlgorithm) -> Option<&String> {
+        self.0.get(algorithm.name())
+    }
+}
+
 /// A trust-on-first-use (TOFU) store for hostnames and their certificate fingerprints.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TofuStore {
-    path: String,
-    known_hosts: HashMap<String, String>, // hostname -> fingerprint
+    path: Option<String>, // none for an in-memory store
+    known_hosts: HashMap<String, Fingerprints>, // hostname -> fingerprints
+    #[serde(skip)]
+    algorithm: FingerprintAlgorithm, // the algorithm new and upgraded entries are recorded with
+    #[serde(skip)]
+    auto_reload: bool,
+    #[serde(skip)]
+    file_version: Option<(SystemTime, u64)>, // modification time and length of the file when last loaded or saved
+}
+
+/// Get the modification time and length of a file, to tell when it changes.
+fn file_version(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
 }
 
 impl TofuStore {
     /// Load a TOFU store from a file.
-    fn load_from_disk(path: String) -> Self {
-        let file = File::open(path).unwrap();
+    fn load_from_disk(path: String) -> Result<Self, String> {
+        let file = File::open(&path).map_err(|e| format!("Failed to open {path}: {e}"))?;
         let reader = BufReader::new(file);
-        let tofu: TofuStore = serde_json::from_reader(reader).unwrap();
+        let tofu: TofuStore = serde_json::from_reader(reader).map_err(|e| format!("Failed to read {path}: {e}"))?;
 
-        tofu
+        // keep saving to where the store was loaded from, even if the file says otherwise
+        Ok(Self { file_version: file_version(&path), path: Some(path), ..tofu })
     }
 
     /// Create a new TOFU store, loading from a file if it already exists.
@@ -39,48 +123,142 @@ impl TofuStore {
             return Err("Tofu store path must end with .json".to_string());
         }
 
-        Ok(if std::fs::exists(&path).unwrap() {
+        if std::fs::exists(&path).map_err(|e| format!("Failed to access {path}: {e}"))? {
             Self::load_from_disk(path)
         } else {
-            Self { path, known_hosts: HashMap::new() }
-        })
+            Ok(Self { path: Some(path), ..Self::in_memory() })
+        }
+    }
+
+    /// Create an empty TOFU store that is never saved to disk.
+    pub fn in_memory() -> Self {
+        Self { path: None, known_hosts: HashMap::new(), algorithm: FingerprintAlgorithm::default(), auto_reload: false, file_version: None }
     }
 
-    /// Save the TOFU store to a file.
-    fn save_to_disk(&self) -> Result<(), String> {
-        let file = File::create(&self.path).map_err(|e| e.to_string())?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &self).map_err(|e| e.to_string())?;
+    /// Save the TOFU store to a file, if it has one.
+    fn save_to_disk(&mut self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &self).map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+        self.file_version = file_version(path);
 
         Ok(())
     }
 
-    /// Save a new host and its fingerprint to the store.
-    fn learn_host(&mut self, hostname: String, fingerprint: String) -> Result<(), String> {
-        self.known_hosts.insert(hostname, fingerprint);
+    /// Set whether the store reloads its file before each verification if another process has changed it.
+    pub fn set_auto_reload(&mut self, auto_reload: bool) {
+        self.auto_reload = auto_reload;
+    }
+
+    /// Reload the known hosts from the store's file if it has changed since it was last loaded or saved, and auto reload is on.
+    pub fn reload_if_changed(&mut self) -> Result<(), String> {
+        let (true, Some(path)) = (self.auto_reload, &self.path) else {
+            return Ok(());
+        };
+        let current_version = file_version(path);
+        if current_version.is_none() || current_version == self.file_version {
+            return Ok(());
+        }
+
+        let reloaded = Self::load_from_disk(path.clone())?;
+        self.known_hosts = reloaded.known_hosts;
+        self.file_version = reloaded.file_version;
+
+        Ok(())
+    }
+
+    /// Set the algorithm new hosts are recorded with. Known hosts are upgraded to it the next time their certificate matches.
+    pub fn set_fingerprint_algorithm(&mut self, algorithm: FingerprintAlgorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// Save a new host and its fingerprint under the store's algorithm to the store.
+    fn learn_host(&mut self, hostname: String, claimed: &Fingerprints) -> Result<(), String> {
+        let fingerprints = claimed.0.iter()
+            .filter(|(algorithm, _)| *algorithm == self.algorithm.name())
+            .map(|(algorithm, fingerprint)| (algorithm.clone(), fingerprint.clone()))
+            .collect();
+        self.known_hosts.insert(hostname, Fingerprints(fingerprints));
 
         self.save_to_disk()
     }
 
-    /// Verify that the fingerprint of the received certificate matches the known fingerprint for the hostname.
-    fn verify_host(&self, hostname: &String, claimed_fingerprint: &String) -> TofuResult {
-        let known_fingerprint = self.known_hosts.get(hostname);
+    /// Record a known host's fingerprint under the store's algorithm, if it isn't already.
+    fn upgrade_host(&mut self, hostname: &str, claimed: &Fingerprints) -> Result<(), String> {
+        let (Some(known), Some(fingerprint)) = (self.known_hosts.get_mut(hostname), claimed.get(self.algorithm)) else {
+            return Ok(());
+        };
+        if known.get(self.algorithm).is_some() {
+            return Ok(());
+        }
+
+        known.0.insert(self.algorithm.name().to_string(), fingerprint.clone());
+        self.save_to_disk()
+    }
+
+    /// Get the path of the file the store is saved to, if it has one.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Get the number of hosts in the store.
+    pub fn host_count(&self) -> usize {
+        self.known_hosts.len()
+    }
+
+    /// Get the known fingerprint for the hostname under an algorithm, if it has one.
+    pub fn known_fingerprint(&self, hostname: &str, algorithm: FingerprintAlgorithm) -> Option<&String> {
+        self.known_hosts.get(hostname)?.get(algorithm)
+    }
+
+    /// Get the algorithm new hosts are recorded with.
+    pub fn fingerprint_algorithm(&self) -> FingerprintAlgorithm {
+        self.algorithm
+    }
+
+    /// Get the known fingerprint for the hostname under the store's algorithm, or under another if it has none under that.
+    pub fn recorded_fingerprint(&self, hostname: &str) -> Option<(FingerprintAlgorithm, &String)> {
+        let known = self.known_hosts.get(hostname)?;
+
+        std::iter::once(self.algorithm)
+            .chain(FingerprintAlgorithm::ALL)
+            .find_map(|algorithm| Some((algorithm, known.get(algorithm)?)))
+    }
 
-        match known_fingerprint {
-            Some(fingerprint) if fingerprint == claimed_fingerprint => TofuResult::Match,
-            Some(_) => TofuResult::Mismatch,
-            None => TofuResult::Unknown,
+    /// Verify that the fingerprints of the received certificate match the known fingerprints for the hostname.
+    /// Every algorithm both have a fingerprint under must agree, and there must be at least one.
+    fn verify_host(&self, hostname: &String, claimed: &Fingerprints) -> TofuResult {
+        let Some(known) = self.known_hosts.get(hostname) else {
+            return TofuResult::Unknown;
+        };
+
+        let comparisons: Vec<bool> = known.0.iter()
+            .filter_map(|(algorithm, fingerprint)| Some(claimed.0.get(algorithm)?.eq_ignore_ascii_case(fingerprint)))
+            .collect();
+        match !comparisons.is_empty() && comparisons.iter().all(|matches| *matches) {
+            true => TofuResult::Match,
+            false => TofuResult::Mismatch,
         }
     }
 
-    /// Verify that the fingerprint of the received certificate matches the known fingerprint for the hostname, or learn the host if it is unknown.
+    /// Verify that the fingerprints of the received certificate match the known fingerprints for the hostname, or learn the host if it is unknown.
     /// If the host is known but the certificate does not match, return a mismatch.
-    pub fn verify_or_learn_host(&mut self, hostname: &String, claimed_fingerprint: &String) -> Result<TofuResult, String> {
-        match self.verify_host(hostname, claimed_fingerprint) {
-            TofuResult::Match => Ok(TofuResult::Match),
+    /// A matching host is also recorded under the store's algorithm if it wasn't already.
+    pub fn verify_or_learn_host(&mut self, hostname: &String, claimed: &Fingerprints) -> Result<TofuResult, String> {
+        match self.verify_host(hostname, claimed) {
+            TofuResult::Match => {
+                self.upgrade_host(hostname, claimed)?;
+
+                Ok(TofuResult::Match)
+            },
             TofuResult::Mismatch => Ok(TofuResult::Mismatch),
             TofuResult::Unknown => {
-                self.learn_host(hostname.clone(), claimed_fingerprint.clone())?;
+                self.learn_host(hostname.clone(), claimed)?;
 
                 Ok(TofuResult::New)
             }
@@ -90,14 +268,56 @@ impl TofuStore {
 }
 
 /// A TOFU `ServerCertVerifier` for TLS connections.
+/// The store is locked for the whole of each verification, so when several handshakes with an unknown host happen at once,
+/// the first one learns its fingerprint and the others are checked against it.
+/// Hosts validated by one of the verifier's certificate authorities are trusted without TOFU.
 #[derive(Debug)]
 pub struct TofuVerifier {
     store: std::sync::RwLock<TofuStore>,
+    authorities: Vec<AuthorityVerifier>,
+    validity_periods: std::sync::Mutex<HashMap<String, (SystemTime, SystemTime)>>, // hostname -> (not before, not after)
+    mismatches: std::sync::Mutex<HashMap<String, CertificateDer<'static>>>, // hostname -> certificate that failed TOFU
+    accepted_once: std::sync::Mutex<HashMap<String, Fingerprints>>, // hostname -> fingerprints to trust for one handshake
+    signature_algorithms: WebPkiSupportedAlgorithms, // the crypto provider's, for checking handshake signatures
 }
 
 impl TofuVerifier {
-    pub fn new(store: TofuStore) -> Self {
-        Self { store: std::sync::RwLock::new(store) }
+    pub fn new(store: TofuStore, authorities: Vec<AuthorityVerifier>) -> Self {
+        Self {
+            store: std::sync::RwLock::new(store),
+            authorities,
+            validity_periods: Default::default(),
+            mismatches: Default::default(),
+            accepted_once: Default::default(),
+            signature_algorithms: CryptoProvider::get_default()
+                .map_or_else(rustls::crypto::aws_lc_rs::default_provider, |provider| (**provider).clone())
+                .signature_verification_algorithms,
+        }
+    }
+
+    /// Lock the store for reading.
+    pub fn store(&self) -> std::sync::RwLockReadGuard<'_, TofuStore> {
+        self.store.read().unwrap()
+    }
+
+    /// Get the validity period of the certificate a host last presented.
+    pub fn validity_period(&self, hostname: &str) -> Option<(SystemTime, SystemTime)> {
+        self.validity_periods.lock().unwrap().get(&hostname.to_ascii_lowercase()).copied()
+    }
+
+    /// Take the certificate a host presented that last failed TOFU, if there is one.
+    pub fn take_mismatch(&self, hostname: &str) -> Option<CertificateDer<'static>> {
+        self.mismatches.lock().unwrap().remove(hostname)
+    }
+
+    /// Trust a certificate for the next handshake with a host, without changing the store.
+    pub fn accept_once(&self, hostname: &str, certificate: &[u8]) {
+        self.accepted_once.lock().unwrap().insert(hostname.to_string(), Fingerprints::of(certificate));
+    }
+
+    /// Trust a certificate for a host from now on, replacing the fingerprint the store knows.
+    pub fn replace_host(&self, hostname: &str, certificate: &[u8]) -> Result<(), String> {
+        self.store.write().unwrap().learn_host(hostname.to_string(), &Fingerprints::of(certificate))
     }
 }
 
@@ -105,10 +325,10 @@ impl rustls::client::danger::ServerCertVerifier for TofuVerifier {
     fn verify_server_cert(
         &self,
         end_entity: &CertificateDer<'_>,
-        _intermediates: &[CertificateDer<'_>],
+        intermediates: &[CertificateDer<'_>],
         server_name: &ServerName<'_>,
         _ocsp_response: &[u8],
-        _now: UnixTime,
+        now: UnixTime,
     ) -> Result<ServerCertVerified, rustls::Error> {
         // get the hostname from the server name
         let hostname = match server_name {
@@ -116,48 +336,253 @@ impl rustls::client::danger::ServerCertVerifier for TofuVerifier {
             _ => return Err(rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidForName)),
         };
 
-        // calculate the certificate fingerprint using SHA-256
-        let fingerprint = Sha256::digest(end_entity.as_ref()).to_vec();
-        let fingerprint = hex::encode(fingerprint);
+        // TOFU doesn't depend on the certificate's dates, but callers can warn about them
+        if let Ok(certificate) = PeerCertificate::from_der(end_entity) {
+            self.validity_periods.lock().unwrap().insert(hostname.to_ascii_lowercase(), (certificate.not_before, certificate.not_after));
+        }
 
-        // verify or learn the host
-        match self.store.write().unwrap().verify_or_learn_host(&hostname, &fingerprint) {
+        // hosts validated by a certificate authority bypass TOFU, and hosts scoped to one must be validated by it
+        let mut authority_error = None;
+        for authority in &self.authorities {
+            match authority.verify(end_entity, intermediates, server_name, &hostname, now) {
+                Some(Ok(())) => return Ok(ServerCertVerified::assertion()),
+                Some(Err(e)) if authority.is_scoped() => authority_error = Some(e),
+                _ => (),
+            }
+        }
+        if let Some(e) = authority_error {
+            return Err(e);
+        }
+
+        // fingerprint the certificate under every algorithm, to compare with whichever the host is known by
+        let fingerprints = Fingerprints::of(end_entity.as_ref());
+
+        // verify or learn the host, first picking up any trust decisions other processes sharing the store made
+        let mut store = self.store.write().unwrap();
+        // a file that can't be read, e.g. because it's being written, is reloaded at the next verification instead
+        let _ = store.reload_if_changed();
+        match store.verify_or_learn_host(&hostname, &fingerprints) {
             Ok(TofuResult::Match) => Ok(ServerCertVerified::assertion()),
             Ok(TofuResult::New) => Ok(ServerCertVerified::assertion()),
-            Ok(TofuResult::Mismatch) => Err(rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidForName)),
+            Ok(TofuResult::Mismatch) => {
+                let mut accepted_once = self.accepted_once.lock().unwrap();
+                if accepted_once.get(&hostname) == Some(&fingerprints) {
+                    accepted_once.remove(&hostname);
+                    return Ok(ServerCertVerified::assertion());
+                }
+
+                self.mismatches.lock().unwrap().insert(hostname, end_entity.clone().into_owned());
+                Err(rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidForName))
+            },
             Ok(TofuResult::Unknown) => unreachable!(),
             Err(_) => Err(rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidForName)),
         }
     }
 
+    // every host must prove it has the key of the certificate it presented, or a copy of a known certificate would pass TOFU
     fn verify_tls12_signature(
         &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
     ) -> Result<HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.signature_algorithms)
     }
 
     fn verify_tls13_signature(
         &self,
-        _message: &[u8],
-        _cert: &CertificateDer<'_>,
-        _dss: &rustls::DigitallySignedStruct,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
     ) -> Result<HandshakeSignatureValid, rustls::Error> {
-        Ok(HandshakeSignatureValid::assertion())
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.signature_algorithms)
     }
 
     fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
-        vec![
-            rustls::SignatureScheme::RSA_PKCS1_SHA256,
-            rustls::SignatureScheme::RSA_PKCS1_SHA384,
-            rustls::SignatureScheme::RSA_PKCS1_SHA512,
-            rustls::SignatureScheme::ECDSA_NISTP256_SHA256,
-            rustls::SignatureScheme::ECDSA_NISTP384_SHA384,
-            rustls::SignatureScheme::ECDSA_NISTP521_SHA512,
-            rustls::SignatureScheme::ED25519,
-            rustls::SignatureScheme::ED448,
-        ]
-    }
-}
\ No newline at end of file
+        self.signature_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    fn sha256(fingerprint: &str) -> Fingerprints {
+        Fingerprints(BTreeMap::from([("sha256".to_string(), fingerprint.to_string())]))
+    }
+
+    #[test]
+    fn concurrent_first_use_learns_once() {
+        let path = std::env::temp_dir().join(format!("yagc_tofu_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let verifier = Arc::new(TofuVerifier::new(TofuStore::new(path.clone()).unwrap(), Vec::new()));
+        let hostname = "example.com".to_string();
+
+        let results: Vec<TofuResult> = (0..8)
+            .map(|i| {
+                let verifier = verifier.clone();
+                let hostname = hostname.clone();
+                // the first two threads present one fingerprint, the rest another
+                let fingerprint = if i < 2 { sha256("a") } else { sha256("b") };
+
+                thread::spawn(move || verifier.store.write().unwrap().verify_or_learn_host(&hostname, &fingerprint).unwrap())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        let new = results.iter().filter(|result| matches!(result, TofuResult::New)).count();
+        assert_eq!(new, 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn hosts_validated_by_an_authority_bypass_tofu() {
+        use crate::client::authority::{tests::{LEAF_PEM, ROOT_PEM}, CertificateAuthority};
+        use rustls::{client::danger::ServerCertVerifier, pki_types::pem::PemObject};
+
+        let verifier = |domains: &[&str]| {
+            let authority = CertificateAuthority::from_pem(ROOT_PEM).unwrap().domains(domains);
+            TofuVerifier::new(TofuStore::in_memory(), vec![AuthorityVerifier::new(authority).unwrap()])
+        };
+        let leaf = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        let verify = |verifier: &TofuVerifier, hostname: &'static str| {
+            verifier.verify_server_cert(&leaf, &[], &ServerName::try_from(hostname).unwrap(), &[], UnixTime::now()).is_ok()
+        };
+
+        let scoped = verifier(&["intranet.test"]);
+        assert!(verify(&scoped, "intranet.test"));
+        assert_eq!(scoped.store().host_count(), 0);
+
+        // the certificate isn't valid for gemini.intranet.test, which the authority covers, so it is rejected rather than learned
+        assert!(!verify(&scoped, "gemini.intranet.test"));
+        assert_eq!(scoped.store().host_count(), 0);
+
+        // other.test isn't covered by the authority, so TOFU applies
+        assert!(verify(&scoped, "other.test"));
+        assert_eq!(scoped.store().host_count(), 1);
+
+        let elsewhere = verifier(&["example.org"]);
+        assert!(verify(&elsewhere, "intranet.test"));
+        assert_eq!(elsewhere.store().host_count(), 1);
+
+        // an authority that isn't scoped falls back to TOFU for certificates it doesn't validate
+        let unscoped = verifier(&[]);
+        assert!(verify(&unscoped, "other.test"));
+        assert_eq!(unscoped.store().host_count(), 1);
+    }
+
+    #[test]
+    fn bad_handshake_signatures_are_rejected_without_an_authority() {
+        use crate::client::authority::tests::LEAF_PEM;
+        use rustls::{client::danger::ServerCertVerifier, internal::msgs::codec::{Codec, Reader}, pki_types::pem::PemObject, SignatureScheme};
+
+        let verifier = TofuVerifier::new(TofuStore::in_memory(), Vec::new());
+        let leaf = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        // an ECDSA P-256 signature scheme with a signature that wasn't made by the leaf's key
+        let mut encoded = Vec::new();
+        SignatureScheme::ECDSA_NISTP256_SHA256.encode(&mut encoded);
+        encoded.extend_from_slice(&[0, 4, 1, 2, 3, 4]);
+        let dss = rustls::DigitallySignedStruct::read(&mut Reader::init(&encoded)).unwrap();
+
+        assert!(verifier.verify_tls12_signature(b"handshake", &leaf, &dss).is_err());
+        assert!(verifier.verify_tls13_signature(b"handshake", &leaf, &dss).is_err());
+        assert!(!verifier.supported_verify_schemes().contains(&SignatureScheme::ED448));
+    }
+
+    #[test]
+    fn mismatches_can_be_accepted_once_or_replaced() {
+        use crate::client::authority::tests::LEAF_PEM;
+        use rustls::{client::danger::ServerCertVerifier, pki_types::pem::PemObject};
+
+        let mut store = TofuStore::in_memory();
+        store.verify_or_learn_host(&"example.com".to_string(), &sha256("abcd")).unwrap();
+        let verifier = TofuVerifier::new(store, Vec::new());
+        let leaf = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        let verify = || verifier.verify_server_cert(&leaf, &[], &ServerName::try_from("example.com").unwrap(), &[], UnixTime::now()).is_ok();
+
+        assert!(!verify());
+        assert_eq!(verifier.take_mismatch("example.com"), Some(leaf.clone()));
+        assert_eq!(verifier.take_mismatch("example.com"), None);
+
+        verifier.accept_once("example.com", &leaf);
+        assert!(verify());
+        assert!(!verify());
+        assert_eq!(verifier.store().known_fingerprint("example.com", FingerprintAlgorithm::Sha256).unwrap(), "abcd");
+
+        verifier.replace_host("example.com", &leaf).unwrap();
+        assert!(verify());
+        assert!(verify());
+    }
+
+    #[test]
+    fn changes_made_by_other_processes_are_reloaded() {
+        let path = std::env::temp_dir().join(format!("yagc_tofu_reload_test_{}.json", std::process::id())).display().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let mut ours = TofuStore::new(path.clone()).unwrap();
+        ours.set_auto_reload(true);
+        ours.verify_or_learn_host(&"example.com".to_string(), &sha256("abcd")).unwrap();
+
+        let mut theirs = TofuStore::new(path.clone()).unwrap();
+        theirs.verify_or_learn_host(&"example.org".to_string(), &sha256("ef01")).unwrap();
+        assert_eq!(ours.known_fingerprint("example.org", FingerprintAlgorithm::Sha256), None);
+
+        ours.reload_if_changed().unwrap();
+        assert_eq!(ours.known_fingerprint("example.org", FingerprintAlgorithm::Sha256).unwrap(), "ef01");
+        assert_eq!(ours.host_count(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn corrupt_store_is_an_error() {
+        let path = std::env::temp_dir().join(format!("yagc_tofu_corrupt_{}.json", std::process::id()));
+        std::fs::write(&path, "{ not json").unwrap();
+
+        assert!(TofuStore::new(path.to_str().unwrap().to_string()).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn in_memory_store_learns_without_saving() {
+        let mut store = TofuStore::in_memory();
+
+        assert!(matches!(store.verify_or_learn_host(&"example.com".to_string(), &sha256("a")), Ok(TofuResult::New)));
+        assert!(matches!(store.verify_or_learn_host(&"example.com".to_string(), &sha256("a")), Ok(TofuResult::Match)));
+    }
+
+    #[test]
+    fn old_entries_load_and_are_upgraded() {
+        let path = std::env::temp_dir().join(format!("yagc_tofu_upgrade_{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let certificate = b"certificate";
+        let sha256 = FingerprintAlgorithm::Sha256.fingerprint(certificate);
+        std::fs::write(&path, format!(r#"{{ "path": null, "known_hosts": {{ "example.com": "{sha256}" }} }}"#)).unwrap();
+
+        let mut store = TofuStore::new(path.clone()).unwrap();
+        store.set_fingerprint_algorithm(FingerprintAlgorithm::Sha512);
+        let hostname = "example.com".to_string();
+        assert!(matches!(store.verify_or_learn_host(&hostname, &Fingerprints::of(b"other")), Ok(TofuResult::Mismatch)));
+        assert!(matches!(store.verify_or_learn_host(&hostname, &Fingerprints::of(certificate)), Ok(TofuResult::Match)));
+
+        let store = TofuStore::new(path.clone()).unwrap();
+        assert_eq!(store.known_fingerprint("example.com", FingerprintAlgorithm::Sha256), Some(&sha256));
+        assert_eq!(store.known_fingerprint("example.com", FingerprintAlgorithm::Sha512), Some(&FingerprintAlgorithm::Sha512.fingerprint(certificate)));
+
+        // a newer algorithm's fingerprint must still match once the older one is gone
+        let only_sha512 = Fingerprints(BTreeMap::from([("sha512".to_string(), FingerprintAlgorithm::Sha512.fingerprint(certificate))]));
+        let mut store = TofuStore::in_memory();
+        store.known_hosts.insert(hostname.clone(), only_sha512);
+        assert!(matches!(store.verify_or_learn_host(&hostname, &Fingerprints::of(certificate)), Ok(TofuResult::Match)));
+        assert!(matches!(store.verify_or_learn_host(&hostname, &Fingerprints::of(b"other")), Ok(TofuResult::Mismatch)));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
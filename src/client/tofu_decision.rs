@@ -0,0 +1,128 @@
+use super::{tofu::FingerprintAlgorithm, certificate::PeerCertificate, Client, ClientError};
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// What to do about a host whose certificate doesn't match the one trusted on first use.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TofuDecision {
+    /// Fail the connection, as happens without a handler.
+    Reject,
+    /// Trust the certificate for one connection, keeping the known fingerprint.
+    AcceptOnce,
+    /// Trust the certificate from now on, replacing the known fingerprint in the TOFU store.
+    Replace,
+}
+
+/// A host's certificate that doesn't match the one trusted on first use, passed to a `TofuDecisionHandler`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TofuMismatch {
+    /// The hostname whose certificate changed.
+    pub hostname: String,
+    /// The algorithm of both fingerprints: the TOFU store's, unless the host is only known under another.
+    pub algorithm: FingerprintAlgorithm,
+    /// The fingerprint the TOFU store knows the host by, if it knows one.
+    pub known_fingerprint: Option<String>,
+    /// The fingerprint of the certificate the host presented.
+    pub fingerprint: String,
+    /// The certificate the host presented.
+    pub certificate: PeerCertificate,
+}
+
+/// The future returned by `TofuDecisionHandler::decide`.
+pub type TofuDecisionFuture<'a> = Pin<Box<dyn Future<Output = TofuDecision> + Send + 'a>>;
+
+/// Decides what to do when a host's certificate doesn't match the TOFU store, e.g. by asking the user.
+/// Set it with `ClientBuilder::tofu_decision_handler`.
+pub trait TofuDecisionHandler: Send + Sync {
+    /// Decide what to do about a mismatched certificate.
+    fn decide<'a>(&'a self, mismatch: &'a TofuMismatch) -> TofuDecisionFuture<'a>;
+}
+
+// so a handler can be shared with the code that set it
+impl<T: TofuDecisionHandler + ?Sized> TofuDecisionHandler for Arc<T> {
+    fn decide<'a>(&'a self, mismatch: &'a TofuMismatch) -> TofuDecisionFuture<'a> {
+        (**self).decide(mismatch)
+    }
+}
+
+impl Client {
+    /// After a failed handshake with a host, ask the client's handler about the certificate if it failed TOFU.
+    /// Returns whether the certificate is now trusted, so the handshake should be tried again.
+    pub(super) async fn decide_tofu_mismatch(&self, hostname: &str) -> Result<bool, ClientError> {
+        // taken even without a handler, so mismatches aren't kept around
+        let Some(certificate) = self.tofu_verifier.take_mismatch(hostname) else {
+            return Ok(false);
+        };
+        let Some(handler) = &self.tofu_decision_handler else {
+            return Ok(false);
+        };
+
+        let (algorithm, known_fingerprint) = {
+            let store = self.tofu_verifier.store();
+            match store.recorded_fingerprint(hostname) {
+                Some((algorithm, fingerprint)) => (algorithm, Some(fingerprint.clone())),
+                None => (store.fingerprint_algorithm(), None),
+            }
+        };
+        let mismatch = TofuMismatch {
+            hostname: hostname.to_string(),
+            algorithm,
+            known_fingerprint,
+            fingerprint: algorithm.fingerprint(&certificate),
+            certificate: PeerCertificate::from_der(&certificate).map_err(ClientError::FailedToConnectToHost)?,
+        };
+        match handler.decide(&mismatch).await {
+            TofuDecision::Reject => Ok(false),
+            TofuDecision::AcceptOnce => {
+                self.tofu_verifier.accept_once(hostname, &certificate);
+                Ok(true)
+            },
+            TofuDecision::Replace => {
+                self.tofu_verifier.replace_host(hostname, &certificate).map_err(ClientError::FailedToConnectToHost)?;
+                Ok(true)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{authority::tests::{LEAF_PEM, ROOT_PEM}, ClientBuilder};
+    use rustls::{client::danger::ServerCertVerifier, pki_types::{pem::PemObject, CertificateDer, ServerName, UnixTime}};
+    use std::sync::Mutex;
+
+    /// Rejects every mismatch, keeping the last one it was asked about.
+    #[derive(Default)]
+    struct Recording(Mutex<Option<TofuMismatch>>);
+
+    impl TofuDecisionHandler for Recording {
+        fn decide<'a>(&'a self, mismatch: &'a TofuMismatch) -> TofuDecisionFuture<'a> {
+            *self.0.lock().unwrap() = Some(mismatch.clone());
+
+            Box::pin(std::future::ready(TofuDecision::Reject))
+        }
+    }
+
+    #[tokio::test]
+    async fn mismatches_are_reported_under_the_stores_algorithm() {
+        let handler = Arc::new(Recording::default());
+        let client = ClientBuilder::new()
+            .in_memory_tofu_store()
+            .fingerprint_algorithm(FingerprintAlgorithm::Sha512)
+            .tofu_decision_handler(handler.clone())
+            .build()
+            .unwrap();
+        let known = CertificateDer::from_pem_slice(LEAF_PEM.as_bytes()).unwrap();
+        let presented = CertificateDer::from_pem_slice(ROOT_PEM.as_bytes()).unwrap();
+
+        client.tofu_verifier.replace_host("example.com", &known).unwrap();
+        let verification = client.tofu_verifier.verify_server_cert(&presented, &[], &ServerName::try_from("example.com").unwrap(), &[], UnixTime::now());
+        assert!(verification.is_err());
+        assert!(!client.decide_tofu_mismatch("example.com").await.unwrap());
+
+        let mismatch = handler.0.lock().unwrap().take().unwrap();
+        assert_eq!(mismatch.algorithm, FingerprintAlgorithm::Sha512);
+        assert_eq!(mismatch.known_fingerprint, Some(FingerprintAlgorithm::Sha512.fingerprint(&known)));
+        assert_eq!(mismatch.fingerprint, FingerprintAlgorithm::Sha512.fingerprint(&presented));
+    }
+}
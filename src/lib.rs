@@ -3,20 +3,59 @@
 //! yagc (*yagsee*) is yet another Gemini client.
 //!
 //! It implements the client standard for the Gemini protocol described at [gemini://geminiprotocol.net/docs/protocol-specification.gmi](gemini://geminiprotocol.net/docs/protocol-specification.gmi).
+//!
+//! The networked client is behind the default `client` feature. Without it, only the URL, request and response types and their parsers are built.
+//! The `blocking` feature adds a synchronous client in the `blocking` module, and the `tracing` feature emits `tracing` spans and events for each stage of a request.
 
 #![warn(missing_docs)]
 #![warn(unused_imports)]
 #![warn(unused_crate_dependencies)]
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "client")]
 mod client;
+pub mod prelude;
+mod request;
+mod response;
 mod url;
 
+#[cfg(feature = "client")]
 pub use client::{
     Client,
+    ClientBuilder,
     ClientError,
+    AddressFamily,
+    FingerprintAlgorithm,
+    CloseBehavior,
     TlsConnection,
     TlsProtocolVersion,
-    request::Request,
-    response::{Response, MimeType},
+    Timeouts,
+    about::VERSION,
+    archive::{RecordedFingerprint, FingerprintStatus},
+    authority::CertificateAuthority,
+    batch::BatchJob,
+    cache::CachePolicy,
+    certificate::{CertificateValidity, PeerCertificate},
+    change::ChangeCheck,
+    cooldown::CooldownPolicy,
+    diagnose::{Diagnosis, DiagnosticStage, StageReport},
+    handle::{FetchHandle, FetchStatus},
+    host_stats::HostStats,
+    identity::{CertificateRejection, CertificateRemediation, CertificateRequest, Identity, IdentityStore},
+    input::InputPrompt,
+    locale::{Catalog, Message},
+    metrics::{ClientMetrics, HostMetrics, LatencyHistogram, MetricsRecorder, RequestRecord, LATENCY_BUCKETS},
+    middleware::Middleware,
+    politeness::{PolitenessPolicy, Priority},
+    redirect::{FetchResult, RedirectPolicy},
+    resolve::{CachingResolver, Resolution, Resolver, StaticResolver, SystemResolver},
+    retry::{RetryPolicy, RetryEvent},
+    socks::Socks5Proxy,
+    strict::SpecViolation,
+    throttle::BandwidthPolicy,
+    tofu_decision::{TofuDecision, TofuDecisionFuture, TofuDecisionHandler, TofuMismatch},
 };
+pub use request::Request;
+pub use response::{Response, MimeType, DecodePolicy, DecodeError, HeaderParseError, HeaderParseErrorKind};
 pub use url::{URL, URLBuilder, Host, Scheme};
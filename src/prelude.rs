@@ -0,0 +1,14 @@
+//! The types needed for typical usage of yagc, for glob importing with `use yagc::prelude::*`.
+
+#[cfg(feature = "client")]
+pub use crate::client::{
+    Client,
+    ClientBuilder,
+    ClientError,
+    Timeouts,
+    redirect::RedirectPolicy,
+    retry::RetryPolicy,
+};
+pub use crate::request::Request;
+pub use crate::response::{Response, MimeType, DecodeError, HeaderParseError};
+pub use crate::url::{URL, URLBuilder, Host, Scheme};
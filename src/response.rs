@@ -0,0 +1,846 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_till, take_until},
+    combinator::opt,
+    sequence::preceded,
+    IResult,
+    Parser,
+};
+use std::fmt;
+
+const DEFAULT_CHARSET: &str = "utf-8";
+const DEFAULT_LANGUAGES: &[&str] = &["en"];
+const MAX_META_LENGTH: usize = 1024;
+/// The longest header line the spec allows, not counting the CRLF: a status code, a space and the longest meta.
+pub(crate) const MAX_HEADER_LENGTH: usize = 3 + MAX_META_LENGTH;
+const KNOWN_STATUS_CODES: &[&[u8]] = &[
+    b"10", b"11",
+    b"20",
+    b"30", b"31",
+    b"40", b"41", b"42", b"43", b"44",
+    b"50", b"51", b"52", b"53", b"59",
+    b"60", b"61", b"62",
+];
+const PREVIEW_LENGTH: usize = 16;
+
+/// A MIME type.
+#[derive(Debug, Clone)]
+pub struct MimeType {
+    /// The type of the MIME type.
+    pub mime_type_type: String,
+    /// The character set of the MIME type.
+    pub charset: String,
+    /// The languages of the MIME type.
+    pub languages: Vec<String>,
+    meta: Option<String>, // the meta it was parsed from, if it was
+}
+
+// two MIME types are equal if they mean the same, however they were written
+impl PartialEq for MimeType {
+    fn eq(&self, other: &Self) -> bool {
+        self.mime_type_type == other.mime_type_type && self.charset == other.charset && self.languages == other.languages
+    }
+}
+
+// a parsed MIME type is written back exactly as it was received, unless its fields were changed since
+impl fmt::Display for MimeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(meta) = &self.meta
+            && Response::mime_type(meta).is_ok_and(|(rest, parsed)| rest.is_empty() && parsed == *self)
+        {
+            return write!(f, "{meta}");
+        }
+
+        write!(f, "{}", self.mime_type_type)?;
+
+        if self.charset != DEFAULT_CHARSET {
+            write!(f, ";charset={}", self.charset)?;
+        }
+
+        if self.languages != DEFAULT_LANGUAGES {
+            write!(f, ";lang={}", self.languages.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MimeType {
+    /// Create a new `MimeType`.
+    pub fn new(mime_type_type: &str, charset: Option<&str>, languages: Option<Vec<&str>>) -> Self {
+        let mime_type_type = mime_type_type.to_string();
+        let charset = charset
+            .unwrap_or(DEFAULT_CHARSET)
+            .to_string();
+        let languages = languages
+            .unwrap_or(DEFAULT_LANGUAGES.to_vec())
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+
+        Self { mime_type_type, charset, languages, meta: None }
+    }
+}
+
+/// A response to a request.
+/// See [gemini://geminiprotocol.net/docs/protocol-specification.gmi](gemini://geminiprotocol.net/docs/protocol-specification.gmi) for more information on what these mean and how they should be handled.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Response {
+    /// A request for input from the user.
+    Input {
+        /// The prompt that should be displayed to the user.
+        prompt: String,
+    },
+    /// A request for sensitive input from the user.
+    SensitiveInput {
+        /// The prompt that should be displayed to the user.
+        prompt: String,
+    },
+    /// A successful response.
+    Success {
+        /// The MIME type of the body.
+        body_mime_type: MimeType,
+        /// The raw bytes of the body of the response.
+        body: Vec<u8>,
+    },
+    /// A temporary redirect to a new URL.
+    TemporaryRedirect {
+        /// The URL to redirect to.
+        url: String,
+    },
+    /// A permanent redirect to a new URL.
+    PermanentRedirect {
+        /// The URL to redirect to.
+        url: String,
+    },
+    /// A temporary failure.
+    TemporaryFailure {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The server is currently unavailable.
+    ServerUnavailable {
+        /// Information about the failure.
+        information: String,
+    },
+    /// A CGI error.
+    CGIError {
+        /// Information about the failure.
+        information: String,
+    },
+    /// A proxy error.
+    ProxyError {
+        /// Information about the failure.
+        information: String,
+    },
+    /// A rate limit was enforced, the server is asking the client to slow down.
+    SlowDown {
+        /// Information about the failure.
+        information: String,
+    },
+    /// A permanent failure.
+    PermanentFailure {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The requested resource was not found.
+    NotFound {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The requested resource is no longer available.
+    Gone {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The proxy request was refused.
+    ProxyRequestRefused {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The request was invalid.
+    BadRequest {
+        /// Information about the failure.
+        information: String,
+    },
+    /// A client certificate is required.
+    ClientCertificateRequired {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The client certificate given was not authorized.
+    CertificateNotAuthorized {
+        /// Information about the failure.
+        information: String,
+    },
+    /// The client certificate given was not valid.
+    CertificateNotValid {
+        /// Information about the failure.
+        information: String,
+    },
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (response_code, response_meta) = self.header();
+        let body = self.body().map(String::from_utf8_lossy).unwrap_or_default();
+
+        write!(f, "{response_code} {response_meta}\r\n{body}")
+    }
+}
+
+impl Response {
+    /// Get the status code and meta of the response header.
+    fn header(&self) -> (u8, String) {
+        match self {
+            Self::Input { prompt }                          => ( 10, prompt.clone() ),
+            Self::SensitiveInput { prompt }                 => ( 11, prompt.clone() ),
+            Self::Success { body_mime_type, .. }            => ( 20, body_mime_type.to_string() ),
+            Self::TemporaryRedirect { url }                 => ( 30, url.clone() ),
+            Self::PermanentRedirect { url }                 => ( 31, url.clone() ),
+            Self::TemporaryFailure { information }          => ( 40, information.clone() ),
+            Self::ServerUnavailable { information }         => ( 41, information.clone() ),
+            Self::CGIError { information }                  => ( 42, information.clone() ),
+            Self::ProxyError { information }                => ( 43, information.clone() ),
+            Self::SlowDown { information }                  => ( 44, information.clone() ),
+            Self::PermanentFailure { information }          => ( 50, information.clone() ),
+            Self::NotFound { information }                  => ( 51, information.clone() ),
+            Self::Gone { information }                      => ( 52, information.clone() ),
+            Self::ProxyRequestRefused { information }       => ( 53, information.clone() ),
+            Self::BadRequest { information }                => ( 59, information.clone() ),
+            Self::ClientCertificateRequired { information } => ( 60, information.clone() ),
+            Self::CertificateNotAuthorized { information }  => ( 61, information.clone() ),
+            Self::CertificateNotValid { information }       => ( 62, information.clone() ),
+        }
+    }
+
+    /// Get the two-digit status code of the response.
+    pub fn status_code(&self) -> u8 {
+        self.header().0
+    }
+
+    /// Serialize the response to the wire format.
+    /// Unlike `to_string`, the body is written out unchanged, so binary bodies survive the round trip.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (response_code, response_meta) = self.header();
+        let mut bytes = format!("{response_code} {response_meta}\r\n").into_bytes();
+
+        if let Some(body) = self.body() {
+            bytes.extend_from_slice(body);
+        }
+
+        bytes
+    }
+}
+
+// this is just a collection of parsers for the different response types
+impl Response {
+    fn input_expected(input: &str) -> IResult<&str, Self> {
+        let (input, response_code) = alt((tag("10"), tag("11"))).parse(input)?;
+        let (input, _) = tag(" ").parse(input)?;
+        let (input, prompt) = take_until("\r\n").parse(input)?;
+        let (input, _) = tag("\r\n").parse(input)?;
+
+        let response = match response_code {
+            "10" => Self::Input { prompt: prompt.to_string() },
+            "11" => Self::SensitiveInput { prompt: prompt.to_string() },
+            _ => unreachable!(),
+        };
+
+        Ok((input, response))
+    }
+
+    fn mime_type_type(input: &str) -> IResult<&str, &str> {
+        take_till(|c| c == ';' || c == '\r').parse(input)
+    }
+
+    fn charset(input: &str) -> IResult<&str, &str> {
+        preceded(
+            (tag(";"), opt(tag(" ")), tag("charset=")),
+            take_till(|c| c == ';' || c == '\r'),
+        ).parse(input)
+    }
+
+    fn languages(input: &str) -> IResult<&str, Vec<&str>> {
+        preceded(
+            (tag(";"), opt(tag(" ")), tag("lang=")),
+            take_till(|c| c == ';' || c == '\r'),
+        )
+        .parse(input)
+        .map(|(input, languages)| {
+            let languages = languages.split(',').collect();
+
+            (input, languages)
+        })
+    }
+
+    fn mime_type(input: &str) -> IResult<&str, MimeType> {
+        (
+            Self::mime_type_type,
+            opt(Self::charset),
+            opt(Self::languages),
+        )
+        .parse(input)
+        .map(|(input, (mime_type_type, charset, languages))| {
+            let mime_type = MimeType::new(mime_type_type, charset, languages);
+
+            (input, mime_type)
+        })
+    }
+
+    // the body is attached afterwards, since it is not necessarily text
+    fn success(input: &str) -> IResult<&str, Self> {
+        let (input, _) = tag("20 ").parse(input)?;
+        let (rest, mut body_mime_type) = Self::mime_type(input)?;
+        body_mime_type.meta = Some(input[..input.len() - rest.len()].to_string());
+        let (input, _) = tag("\r\n").parse(rest)?;
+
+        let response = Self::Success { body_mime_type, body: Vec::new() };
+
+        Ok((input, response))
+    }
+
+    fn temporary_redirect(input: &str) -> IResult<&str, Self> {
+        let (input, response_code) = alt((tag("30"), tag("31"))).parse(input)?;
+        let (input, _) = tag(" ").parse(input)?;
+        let (input, url) = take_until("\r\n").parse(input)?;
+        let url = url.to_string();
+        let (input, _) = tag("\r\n").parse(input)?;
+
+        let response = match response_code {
+            "30" => Self::TemporaryRedirect { url },
+            "31" => Self::PermanentRedirect { url },
+            _ => unreachable!(),
+        };
+
+        Ok((input, response))
+    }
+
+    fn temporary_failure(input: &str) -> IResult<&str, Self> {
+        let (input, response_code) = alt((tag("40"), tag("41"), tag("42"), tag("43"), tag("44"))).parse(input)?;
+        let (input, _) = tag(" ").parse(input)?;
+        let (input, information) = take_until("\r\n").parse(input)?;
+        let information = information.to_string();
+        let (input, _) = tag("\r\n").parse(input)?;
+
+        let response = match response_code {
+            "40" => Self::TemporaryFailure { information },
+            "41" => Self::ServerUnavailable { information },
+            "42" => Self::CGIError { information },
+            "43" => Self::ProxyError { information },
+            "44" => Self::SlowDown { information },
+            _ => unreachable!(),
+        };
+
+        Ok((input, response))
+    }
+
+    fn permanent_failure(input: &str) -> IResult<&str, Self> {
+        let (input, response_code) = alt((tag("50"), tag("51"), tag("52"), tag("53"), tag("59"))).parse(input)?;
+        let (input, _) = tag(" ").parse(input)?;
+        let (input, information) = take_until("\r\n").parse(input)?;
+        let information = information.to_string();
+        let (input, _) = tag("\r\n").parse(input)?;
+
+        let response = match response_code {
+            "50" => Self::PermanentFailure { information },
+            "51" => Self::NotFound { information },
+            "52" => Self::Gone { information },
+            "53" => Self::ProxyRequestRefused { information },
+            "59" => Self::BadRequest { information },
+            _ => unreachable!(),
+        };
+
+        Ok((input, response))
+    }
+
+    fn client_certificate_required(input: &str) -> IResult<&str, Self> {
+        let (input, response_code) = alt((tag("60"), tag("61"), tag("62"))).parse(input)?;
+        let (input, _) = tag(" ").parse(input)?;
+        let (input, information) = take_until("\r\n").parse(input)?;
+        let information = information.to_string();
+        let (input, _) = tag("\r\n").parse(input)?;
+
+        let response = match response_code {
+            "60" => Self::ClientCertificateRequired { information },
+            "61" => Self::CertificateNotAuthorized { information },
+            "62" => Self::CertificateNotValid { information },
+            _ => unreachable!(),
+        };
+
+        Ok((input, response))
+    }
+
+    // the first digit of the status code picks the parser, so only one of them has to run
+    fn from_str(input: &str) -> IResult<&str, Self> {
+        match input.as_bytes().first() {
+            Some(b'1') => Self::input_expected(input),
+            Some(b'2') => Self::success(input),
+            Some(b'3') => Self::temporary_redirect(input),
+            Some(b'4') => Self::temporary_failure(input),
+            Some(b'5') => Self::permanent_failure(input),
+            Some(b'6') => Self::client_certificate_required(input),
+            _ => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Alt))),
+        }
+    }
+}
+
+/// The kind of problem found in a response header.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum HeaderParseErrorKind {
+    /// The status code is not two digits, or is not a known status code.
+    BadStatusDigits,
+    /// The status code is not followed by a space.
+    MissingSpace,
+    /// The header is not terminated by CRLF.
+    MissingCrlf,
+    /// The meta is longer than 1024 bytes, or the header is longer than the given limit.
+    MetaTooLong,
+    /// The meta is not valid for the status code, e.g. a malformed MIME type or invalid UTF-8.
+    InvalidMeta,
+    /// A response other than a success has a body.
+    UnexpectedBody,
+}
+
+/// An error that can occur when parsing a response header.
+#[derive(Debug, PartialEq)]
+pub struct HeaderParseError {
+    /// The kind of problem found.
+    pub kind: HeaderParseErrorKind,
+    /// The byte offset in the response where the problem was found.
+    pub offset: usize,
+    /// A hex preview of up to 16 bytes of the response, starting at `offset`.
+    pub preview: String,
+}
+
+impl HeaderParseError {
+    fn new(kind: HeaderParseErrorKind, input: &[u8], offset: usize) -> Self {
+        let end = input.len().min(offset + PREVIEW_LENGTH);
+        let preview = input.get(offset..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        Self { kind, offset, preview }
+    }
+}
+
+impl fmt::Display for HeaderParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at byte {} (bytes: {})", self.kind, self.offset, self.preview)
+    }
+}
+
+impl TryFrom<&[u8]> for Response {
+    type Error = HeaderParseError;
+
+    fn try_from(input: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_bytes_with_limit(input, MAX_HEADER_LENGTH)
+    }
+}
+
+impl TryFrom<&str> for Response {
+    type Error = HeaderParseError;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Self::try_from(input.as_bytes())
+    }
+}
+
+impl Response {
+    /// Parse a response like `Response::try_from`, but allow a header line (not counting the CRLF) of up to `max_header_length` bytes.
+    pub fn from_bytes_with_limit(input: &[u8], max_header_length: usize) -> Result<Self, HeaderParseError> {
+        use HeaderParseErrorKind::*;
+
+        // check the structure of the header first, so problems can be pointed at precisely
+        let Some(crlf_position) = input.windows(2).position(|window| window == b"\r\n") else {
+            return Err(HeaderParseError::new(MissingCrlf, input, input.len()));
+        };
+        if input.len() < 2 || !KNOWN_STATUS_CODES.contains(&&input[..2]) {
+            return Err(HeaderParseError::new(BadStatusDigits, input, 0));
+        }
+        if input.get(2) != Some(&b' ') {
+            return Err(HeaderParseError::new(MissingSpace, input, 2));
+        }
+        if crlf_position > max_header_length {
+            return Err(HeaderParseError::new(MetaTooLong, input, max_header_length));
+        }
+
+        // split the header line off before parsing, the body may be binary
+        let (header, body) = input.split_at(crlf_position + 2);
+        let header = std::str::from_utf8(header)
+            .map_err(|e| HeaderParseError::new(InvalidMeta, input, e.valid_up_to()))?;
+
+        let (rest, response) = Self::from_str(header).map_err(|e| {
+            let offset = match e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => header.len() - e.input.len(),
+                nom::Err::Incomplete(_) => header.len(),
+            };
+
+            HeaderParseError::new(InvalidMeta, input, offset)
+        })?;
+
+        if !rest.is_empty() {
+            return Err(HeaderParseError::new(InvalidMeta, input, header.len() - rest.len()));
+        }
+
+        match response {
+            Self::Success { body_mime_type, .. } => Ok(Self::Success { body_mime_type, body: body.to_vec() }),
+            _ if !body.is_empty() => Err(HeaderParseError::new(UnexpectedBody, input, header.len())),
+            response => Ok(response),
+        }
+    }
+}
+
+/// How bytes that are not valid in the body's charset are handled when decoding it as text.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum DecodePolicy {
+    /// Replace each invalid sequence with U+FFFD.
+    Replace,
+    /// Fail on the first invalid sequence.
+    Error,
+    /// Drop invalid sequences.
+    Skip,
+}
+
+/// An error that can occur when decoding a response body as text.
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    /// The response does not have a body.
+    NoBody,
+    /// The charset of the body is not supported.
+    UnsupportedCharset(String),
+    /// The body contains a sequence that is not valid in its charset.
+    InvalidBytes {
+        /// The byte offset of the invalid sequence in the body.
+        offset: usize,
+    },
+}
+
+fn decode_utf8(mut bytes: &[u8], policy: DecodePolicy) -> Result<String, DecodeError> {
+    let mut text = String::with_capacity(bytes.len());
+    let mut offset = 0;
+
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                return Ok(text);
+            },
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // `valid_up_to` guarantees this prefix is valid
+                text.push_str(std::str::from_utf8(valid).unwrap());
+
+                match policy {
+                    DecodePolicy::Replace => text.push(char::REPLACEMENT_CHARACTER),
+                    DecodePolicy::Skip => (),
+                    DecodePolicy::Error => return Err(DecodeError::InvalidBytes { offset: offset + valid.len() }),
+                }
+
+                let invalid_length = e.error_len().unwrap_or(rest.len());
+                offset += valid.len() + invalid_length;
+                bytes = &rest[invalid_length..];
+            },
+        }
+    }
+}
+
+fn decode_ascii(bytes: &[u8], policy: DecodePolicy) -> Result<String, DecodeError> {
+    let mut text = String::with_capacity(bytes.len());
+
+    for (offset, byte) in bytes.iter().enumerate() {
+        if byte.is_ascii() {
+            text.push(*byte as char);
+        } else {
+            match policy {
+                DecodePolicy::Replace => text.push(char::REPLACEMENT_CHARACTER),
+                DecodePolicy::Skip => (),
+                DecodePolicy::Error => return Err(DecodeError::InvalidBytes { offset }),
+            }
+        }
+    }
+
+    Ok(text)
+}
+
+impl Response {
+    /// Get the raw body of the response, if it has one.
+    pub fn body(&self) -> Option<&[u8]> {
+        match self {
+            Self::Success { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    /// Decode the body as UTF-8 text according to its charset, handling invalid bytes with the given policy.
+    /// The supported charsets are UTF-8, US-ASCII and ISO-8859-1.
+    pub fn text_with(&self, policy: DecodePolicy) -> Result<String, DecodeError> {
+        let Self::Success { body_mime_type, body } = self else {
+            return Err(DecodeError::NoBody);
+        };
+
+        match body_mime_type.charset.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => decode_utf8(body, policy),
+            "us-ascii" | "ascii" => decode_ascii(body, policy),
+            // every byte is a valid latin-1 character, and maps directly to the same code point
+            "iso-8859-1" | "latin1" => Ok(body.iter().map(|byte| *byte as char).collect()),
+            charset => Err(DecodeError::UnsupportedCharset(charset.to_string())),
+        }
+    }
+
+    /// Decode the body as text, replacing invalid sequences with U+FFFD.
+    pub fn text_lossy(&self) -> Result<String, DecodeError> {
+        self.text_with(DecodePolicy::Replace)
+    }
+
+    /// Decode the body as text, failing if it contains any invalid sequence.
+    pub fn text_strict(&self) -> Result<String, DecodeError> {
+        self.text_with(DecodePolicy::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input() {
+        let response = Response::try_from("10 What is the capital of France?\r\n");
+        assert_eq!(response, Ok(Response::Input { prompt: "What is the capital of France?".to_string() }));
+    }
+
+    #[test]
+    fn sensitive_input() {
+        let response = Response::try_from("11 What is the capital of France?\r\n");
+        assert_eq!(response, Ok(Response::SensitiveInput { prompt: "What is the capital of France?".to_string() }));
+    }
+
+    #[test]
+    fn success_without_charset() {
+        let response = Response::try_from("20 text/plain\r\nHello, world!");
+        assert_eq!(response, Ok(Response::Success {
+            body_mime_type: MimeType::new(
+                "text/plain",
+                Some("utf-8"),
+                Some(vec!["en"]),
+            ),
+            body: b"Hello, world!".to_vec(),
+        }));
+    }
+
+    #[test]
+    fn success_with_charset() {
+        let response = Response::try_from("20 text/plain;charset=us-ascii\r\nHello, world!");
+        assert_eq!(response, Ok(Response::Success {
+            body_mime_type: MimeType::new(
+                "text/plain",
+                Some("us-ascii"),
+                Some(vec!["en"]),
+            ),
+            body: b"Hello, world!".to_vec(),
+        }));
+    }
+
+    #[test]
+    fn success_with_languages() {
+        let response = Response::try_from("20 text/plain;lang=fr,zh-Hans-CN\r\nHello, world!");
+        assert_eq!(response, Ok(Response::Success {
+            body_mime_type: MimeType::new(
+                "text/plain",
+                Some("utf-8"),
+                Some(vec!["fr", "zh-Hans-CN"]),
+            ),
+            body: b"Hello, world!".to_vec(),
+        }));
+    }
+
+    #[test]
+    fn temporary_redirect() {
+        let response = Response::try_from("30 https://example.com\r\n");
+        assert_eq!(response, Ok(Response::TemporaryRedirect { url: "https://example.com".to_string() }));
+    }
+
+    #[test]
+    fn permanent_redirect() {
+        let response = Response::try_from("31 https://example.com\r\n");
+        assert_eq!(response, Ok(Response::PermanentRedirect { url: "https://example.com".to_string() }));
+    }
+
+    #[test]
+    fn temporary_failure() {
+        let response = Response::try_from("40 The server is temporarily unable to service your request due to maintenance downtime or capacity problems. Please try again later.\r\n");
+        assert_eq!(response, Ok(Response::TemporaryFailure { information: "The server is temporarily unable to service your request due to maintenance downtime or capacity problems. Please try again later.".to_string() }));
+    }
+
+    #[test]
+    fn server_unavailable() {
+        let response = Response::try_from("41 The server is currently unavailable. Please try again later.\r\n");
+        assert_eq!(response, Ok(Response::ServerUnavailable { information: "The server is currently unavailable. Please try again later.".to_string() }));
+    }
+
+    #[test]
+    fn cgi_error() {
+        let response = Response::try_from("42 meow\r\n");
+        assert_eq!(response, Ok(Response::CGIError { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn proxy_error() {
+        let response = Response::try_from("43 meow\r\n");
+        assert_eq!(response, Ok(Response::ProxyError { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn slow_down() {
+        let response = Response::try_from("44 meow\r\n");
+        assert_eq!(response, Ok(Response::SlowDown { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn permanent_failure() {
+        let response = Response::try_from("50 meow\r\n");
+        assert_eq!(response, Ok(Response::PermanentFailure { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn not_found() {
+        let response = Response::try_from("51 meow\r\n");
+        assert_eq!(response, Ok(Response::NotFound { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn gone() {
+        let response = Response::try_from("52 meow\r\n");
+        assert_eq!(response, Ok(Response::Gone { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn proxy_request_refused() {
+        let response = Response::try_from("53 meow\r\n");
+        assert_eq!(response, Ok(Response::ProxyRequestRefused { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn bad_request() {
+        let response = Response::try_from("59 meow\r\n");
+        assert_eq!(response, Ok(Response::BadRequest { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn client_certificate_required() {
+        let response = Response::try_from("60 meow\r\n");
+        assert_eq!(response, Ok(Response::ClientCertificateRequired { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn certificate_not_authorized() {
+        let response = Response::try_from("61 meow\r\n");
+        assert_eq!(response, Ok(Response::CertificateNotAuthorized { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn certificate_not_valid() {
+        let response = Response::try_from("62 meow\r\n");
+        assert_eq!(response, Ok(Response::CertificateNotValid { information: "meow".to_string() }));
+    }
+
+    #[test]
+    fn success_with_binary_body() {
+        let response = Response::try_from(b"20 image/png\r\n\x89PNG\xff".as_slice());
+        assert_eq!(response.unwrap().body(), Some(b"\x89PNG\xff".as_slice()));
+    }
+
+    #[test]
+    fn text_policies() {
+        let response = Response::try_from(b"20 text/plain\r\nab\xffcd".as_slice()).unwrap();
+
+        assert_eq!(response.text_lossy(), Ok("ab\u{FFFD}cd".to_string()));
+        assert_eq!(response.text_with(DecodePolicy::Skip), Ok("abcd".to_string()));
+        assert_eq!(response.text_strict(), Err(DecodeError::InvalidBytes { offset: 2 }));
+    }
+
+    #[test]
+    fn text_with_charset() {
+        let response = Response::try_from(b"20 text/plain;charset=ISO-8859-1\r\ncaf\xe9".as_slice()).unwrap();
+        assert_eq!(response.text_strict(), Ok("café".to_string()));
+
+        let response = Response::try_from(b"20 text/plain;charset=us-ascii\r\ncaf\xe9".as_slice()).unwrap();
+        assert_eq!(response.text_strict(), Err(DecodeError::InvalidBytes { offset: 3 }));
+
+        let response = Response::try_from("20 text/plain;charset=koi8-r\r\nmeow").unwrap();
+        assert_eq!(response.text_lossy(), Err(DecodeError::UnsupportedCharset("koi8-r".to_string())));
+    }
+
+    #[test]
+    fn text_without_body() {
+        let response = Response::try_from("51 meow\r\n").unwrap();
+        assert_eq!(response.text_lossy(), Err(DecodeError::NoBody));
+    }
+
+    #[test]
+    fn to_bytes_round_trip() {
+        let responses: &[&[u8]] = &[
+            b"20 text/gemini\r\n# Hello\r\n",
+            b"20 image/png\r\n\x89PNG\xff\x00",
+            b"20 text/plain;charset=us-ascii;lang=fr,de\r\nBonjour",
+            b"20 text/gemini; charset=utf-8\r\n# Hello",
+            b"20 text/gemini;charset=UTF-8;lang=en\r\n# Hello",
+            b"31 gemini://example.com/new\r\n",
+            b"51 meow\r\n",
+        ];
+
+        for bytes in responses {
+            let response = Response::try_from(*bytes).unwrap();
+            assert_eq!(response.to_bytes(), bytes.to_vec());
+        }
+
+        // a changed MIME type is written from its fields
+        let Response::Success { mut body_mime_type, body } = Response::try_from("20 text/gemini; charset=utf-8\r\n").unwrap() else {
+            unreachable!();
+        };
+        body_mime_type.languages = vec!["fr".to_string()];
+        assert_eq!(Response::Success { body_mime_type, body }.to_bytes(), b"20 text/gemini;lang=fr\r\n");
+    }
+
+    #[test]
+    fn header_parse_errors() {
+        let kind = |input: &[u8]| Response::try_from(input).map_err(|e| (e.kind, e.offset));
+
+        assert_eq!(kind(b"70 meow\r\n"), Err((HeaderParseErrorKind::BadStatusDigits, 0)));
+        assert_eq!(kind(b"2x meow\r\n"), Err((HeaderParseErrorKind::BadStatusDigits, 0)));
+        assert_eq!(kind(b"51meow\r\n"), Err((HeaderParseErrorKind::MissingSpace, 2)));
+        assert_eq!(kind(b"51 meow\n"), Err((HeaderParseErrorKind::MissingCrlf, 8)));
+        assert_eq!(kind(b"51 meow\r\nbody"), Err((HeaderParseErrorKind::UnexpectedBody, 9)));
+        assert_eq!(kind(b"20 text/plain;format=flowed\r\n"), Err((HeaderParseErrorKind::InvalidMeta, 13)));
+        assert_eq!(kind(b"51 m\xffow\r\n"), Err((HeaderParseErrorKind::InvalidMeta, 4)));
+
+        let long_meta = format!("51 {}\r\n", "a".repeat(MAX_META_LENGTH + 1));
+        assert_eq!(kind(long_meta.as_bytes()), Err((HeaderParseErrorKind::MetaTooLong, 3 + MAX_META_LENGTH)));
+    }
+
+    #[test]
+    fn header_length_limit() {
+        let long_meta = format!("51 {}\r\n", "a".repeat(MAX_META_LENGTH + 1));
+
+        assert!(Response::from_bytes_with_limit(long_meta.as_bytes(), 2 * MAX_HEADER_LENGTH).is_ok());
+        assert_eq!(
+            Response::from_bytes_with_limit(b"51 meow\r\n", 5).map_err(|e| (e.kind, e.offset)),
+            Err((HeaderParseErrorKind::MetaTooLong, 5)),
+        );
+    }
+
+    #[test]
+    fn header_parse_error_preview() {
+        let error = Response::try_from("51meow\r\n").unwrap_err();
+        assert_eq!(error.preview, "6d 65 6f 77 0d 0a");
+    }
+
+    #[test]
+    fn invalid_response() {
+        let response = Response::try_from("70 meow\r\n");
+        assert!(response.is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use nom::{
     branch::alt, bytes::complete::{tag, take_while}, character::digit1, combinator::opt, multi::{many0, many1}, sequence::{preceded, terminated}, IResult, Parser
 };
@@ -12,28 +13,85 @@ const DEFAULT_SCHEME: Scheme = Scheme::Gemini;
 pub enum Scheme {
     Gemini,
     About,
+    Http,
+    Https,
+    Gopher,
 }
 
-impl ToString for Scheme {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self {
             Scheme::Gemini => "gemini",
             Scheme::About => "about",
-        }.to_string()
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+            Scheme::Gopher => "gopher",
+        };
+
+        write!(f, "{scheme}")
+    }
+}
+
+impl Scheme {
+    /// The port used when a URL with this scheme doesn't give one.
+    fn default_port(self) -> u16 {
+        match self {
+            Scheme::Gemini | Scheme::About => DEFAULT_PORT,
+            Scheme::Http => 80,
+            Scheme::Https => 443,
+            Scheme::Gopher => 70,
+        }
     }
 }
 
 /// The host part of a URL.
 #[allow(missing_docs)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Host {
     pub name: String,
     pub port: u16,
 }
 
-impl ToString for Host {
-    fn to_string(&self) -> String {
-        format!("{}:{}", self.name, self.port)
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // an IPv6 name is wrapped in brackets so its colons aren't taken for the port's
+        match self.name.contains(':') {
+            true => write!(f, "[{}]:{}", self.name, self.port),
+            false => write!(f, "{}:{}", self.name, self.port),
+        }
+    }
+}
+
+// <host> := <name> ( ":" <port> )?, where an IPv6 name is wrapped in brackets
+impl TryFrom<&str> for Host {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (name, port) = if let Some(rest) = value.strip_prefix('[') {
+            let (name, rest) = rest.split_once(']').ok_or(format!("Invalid host: {value}"))?;
+            let port = match rest {
+                "" => None,
+                _ => Some(rest.strip_prefix(':').ok_or(format!("Invalid host: {value}"))?),
+            };
+
+            (name, port)
+        } else {
+            match value.split_once(':') {
+                Some((name, port)) => (name, Some(port)),
+                None => (value, None),
+            }
+        };
+
+        if name.is_empty() || name.contains(['/', '?']) {
+            return Err(format!("Invalid host: {value}"));
+        }
+
+        let port = match port {
+            Some(port) => port.parse::<u16>().map_err(|_| format!("Invalid port: {port}"))?,
+            None => DEFAULT_PORT,
+        };
+
+        Ok(Host { name: name.to_string(), port })
     }
 }
 
@@ -50,9 +108,9 @@ pub struct URL {
     pub query: Option<String>,
 }
 
-impl ToString for URL {
-    fn to_string(&self) -> String {
-        let mut uri = format!("{}:", self.scheme.to_string());
+impl fmt::Display for URL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut uri = format!("{}:", self.scheme);
 
         if let Some(host) = &self.host {
             uri.push_str("//");
@@ -60,19 +118,186 @@ impl ToString for URL {
         }
 
         if !self.path.starts_with('/') {
-            uri.push_str("/");
+            uri.push('/');
         }
         uri.push_str(&self.path);
 
         if let Some(query) = &self.query {
-            uri.push_str("?");
-            uri.push_str(&query);
+            uri.push('?');
+            uri.push_str(query);
         }
 
-        uri
+        write!(f, "{uri}")
     }
 }
 
+impl URL {
+    /// Get a copy of the URL with its query set to the percent-encoded input, as when answering an input prompt.
+    pub fn with_query(&self, input: &str) -> URL {
+        let mut query = String::new();
+        for byte in input.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                query.push(byte as char);
+            } else {
+                query.push_str(&format!("%{byte:02X}"));
+            }
+        }
+
+        URL { query: Some(query), ..self.clone() }
+    }
+
+    /// Resolve a URL reference, which may be absolute or relative, against this URL as described in RFC 3986.
+    /// Any fragment is dropped, since it is never sent to the server.
+    pub fn join(&self, reference: &str) -> Result<URL, String> {
+        if reference.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(format!("Invalid URL reference: {reference:?}"));
+        }
+        let reference = reference.split_once('#').map_or(reference, |(reference, _)| reference);
+
+        // a reference with its own scheme is absolute
+        let has_scheme = reference.split_once(':').is_some_and(|(scheme, _)| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        });
+        if has_scheme {
+            return URL::try_from(reference);
+        }
+        if reference.starts_with("//") {
+            return URL::try_from(format!("{}:{reference}", self.scheme).as_str());
+        }
+
+        let (path, query) = match reference.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (reference, None),
+        };
+        let (path, query) = if path.is_empty() {
+            (self.path.clone(), query.or_else(|| self.query.clone()))
+        } else if path.starts_with('/') {
+            (remove_dot_segments(path), query)
+        } else {
+            let directory = self.path.rfind('/').map_or("/", |end| &self.path[..=end]);
+            let directory = if directory.starts_with('/') { directory.to_string() } else { format!("/{directory}") };
+
+            (remove_dot_segments(&format!("{directory}{path}")), query)
+        };
+
+        Ok(URL { scheme: self.scheme, host: self.host.clone(), path, query })
+    }
+
+    /// Get the URL of the directory containing this URL's path, without the query, or `None` at the root.
+    /// The parent of `/a/b` and of `/a/b/` is `/a/`.
+    pub fn parent(&self) -> Option<URL> {
+        let path = self.path.strip_suffix('/').unwrap_or(&self.path);
+        let end = path.rfind('/')?;
+
+        Some(URL { path: path[..=end].to_string(), query: None, ..self.clone() })
+    }
+
+    /// Get the URL of a segment inside this URL's path, treating the path as a directory and dropping the query.
+    /// The segment is percent-encoded, so it can't contain `/` or other delimiters.
+    pub fn join_segment(&self, segment: &str) -> URL {
+        let directory = self.with_trailing_slash();
+        let mut path = directory.path;
+        for byte in segment.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+                path.push(byte as char);
+            } else {
+                path.push_str(&format!("%{byte:02X}"));
+            }
+        }
+
+        URL { path, query: None, ..directory }
+    }
+
+    /// Get the percent-decoded last segment of the path, or `None` if the path is directory-like.
+    pub fn file_name(&self) -> Option<String> {
+        let segment = self.path.rsplit('/').next().filter(|segment| !segment.is_empty())?;
+
+        Some(percent_decode(segment))
+    }
+
+    /// Get the extension of the file name, without the dot. Names that start with a dot, like `.hidden`, have no extension.
+    pub fn extension(&self) -> Option<String> {
+        let file_name = self.file_name()?;
+        let (stem, extension) = file_name.rsplit_once('.')?;
+        if stem.is_empty() || extension.is_empty() {
+            return None;
+        }
+
+        Some(extension.to_string())
+    }
+
+    /// Get whether the path refers to a directory, i.e. it is empty or ends with `/`.
+    pub fn is_directory_like(&self) -> bool {
+        self.path.is_empty() || self.path.ends_with('/')
+    }
+
+    /// Get a copy of the URL without its query, e.g. for logs and errors, where a query answering a sensitive input prompt mustn't appear.
+    pub fn without_query(&self) -> URL {
+        URL { query: None, ..self.clone() }
+    }
+
+    /// Get a copy of the URL with `/` appended to its path if it doesn't already end with one.
+    pub fn with_trailing_slash(&self) -> URL {
+        let mut url = self.clone();
+        if !url.path.ends_with('/') {
+            url.path.push('/');
+        }
+
+        url
+    }
+}
+
+/// Decode the percent-encoded bytes in a string, replacing invalid UTF-8. Malformed escapes are kept as they are.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = bytes.get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            },
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Remove the `.` and `..` segments from an absolute path.
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut output = Vec::new();
+
+    for (index, segment) in segments.iter().enumerate() {
+        let is_last = index == segments.len() - 1;
+
+        match *segment {
+            "." => (),
+            ".." => {
+                output.pop();
+            },
+            segment => output.push(segment),
+        }
+
+        // a path ending in a dot segment refers to a directory
+        if is_last && (*segment == "." || *segment == "..") {
+            output.push("");
+        }
+    }
+
+    format!("/{}", output.join("/"))
+}
+
 // (kinda jank but it works)
 // <url> := <scheme> ":" ( "//" <hostname> ( ":" <port> )? )? <path> ( "?" <query> )?
 // <hostname> := <url char>+ ( "." <url char>+ )+
@@ -83,6 +308,9 @@ impl URL {
             alt((
                 tag("gemini"),
                 tag("about"),
+                tag("https"),
+                tag("http"),
+                tag("gopher"),
             )),
             tag(":"),
         )
@@ -91,6 +319,9 @@ impl URL {
             let scheme = match scheme {
                 "gemini" => Scheme::Gemini,
                 "about" => Scheme::About,
+                "https" => Scheme::Https,
+                "http" => Scheme::Http,
+                "gopher" => Scheme::Gopher,
                 _ => unreachable!(),
             };
 
@@ -128,7 +359,8 @@ impl URL {
             })
     }
 
-    fn host(input: &str) -> IResult<&str, Host> {
+    /// Parse a hostname and port, if the URL gives one.
+    fn host(input: &str) -> IResult<&str, (String, Option<u16>)> {
         preceded(
             tag("//"),
             (
@@ -140,11 +372,6 @@ impl URL {
             )
         )
         .parse(input)
-        .map(|(input, (hostname, port))| {
-            let port = port.unwrap_or(DEFAULT_PORT);
-
-            (input, Host { name: hostname, port })
-        })
     }
 
     fn query(input: &str) -> IResult<&str, String> {
@@ -176,7 +403,7 @@ impl URL {
         })
     }
 
-    fn url(input: &str) -> IResult<&str, Self> {
+    fn parse(input: &str) -> IResult<&str, Self> {
         (
             opt(Self::scheme),
             opt(Self::host),
@@ -210,8 +437,9 @@ impl URL {
                 url_builder = url_builder.host(host);
                 url_builder = url_builder.path(path_part);
             } else {
-                if let Some(host) = host {
-                    url_builder = url_builder.host(host);
+                if let Some((name, port)) = host {
+                    let port = port.unwrap_or_else(|| scheme.unwrap_or(DEFAULT_SCHEME).default_port());
+                    url_builder = url_builder.host(Host { name, port });
                 }
 
                 if !path.is_empty() {
@@ -234,7 +462,7 @@ impl TryFrom<&str> for URL {
     type Error = String;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let (input, url) = Self::url(value).map_err(|e| e.to_string())?;
+        let (input, url) = Self::parse(value).map_err(|e| e.to_string())?;
 
         if !input.is_empty() {
             Err(format!("Invalid URL: {value}"))
@@ -252,6 +480,12 @@ pub struct URLBuilder {
     query: Option<String>,
 }
 
+impl Default for URLBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[allow(dead_code)]
 impl URLBuilder {
     /// Create a new `URLBuilder`.
@@ -409,6 +643,82 @@ mod tests {
         }));
     }
 
+    #[test]
+    fn host_from_str() {
+        assert_eq!(Host::try_from("example.com:1966"), Ok(Host { name: "example.com".to_string(), port: 1966 }));
+        assert_eq!(Host::try_from("localhost"), Ok(Host { name: "localhost".to_string(), port: DEFAULT_PORT }));
+        assert_eq!(Host::try_from("[::1]:1966"), Ok(Host { name: "::1".to_string(), port: 1966 }));
+        assert_eq!(Host::try_from("[::1]"), Ok(Host { name: "::1".to_string(), port: DEFAULT_PORT }));
+
+        for host in ["example.com:1966", "[::1]:1966"] {
+            assert_eq!(Host::try_from(host).unwrap().to_string(), host);
+        }
+        let url = URL { scheme: Scheme::Gemini, host: Some(Host::try_from("[::1]").unwrap()), path: "/".to_string(), query: None };
+        assert_eq!(url.to_string(), "gemini://[::1]:1965/");
+    }
+
+    #[test]
+    fn invalid_host() {
+        assert!(Host::try_from("").is_err());
+        assert!(Host::try_from("example.com:meow").is_err());
+        assert!(Host::try_from("example.com:99999").is_err());
+        assert!(Host::try_from("[::1").is_err());
+        assert!(Host::try_from("example.com/path").is_err());
+    }
+
+    #[test]
+    fn url_with_query() {
+        let url = URL::try_from("gemini://example.com/search?old").unwrap();
+
+        assert_eq!(url.with_query("cats & dogs?").to_string(), "gemini://example.com:1965/search?cats%20%26%20dogs%3F");
+        assert_eq!(url.with_query("naïve").query, Some("na%C3%AFve".to_string()));
+    }
+
+    #[test]
+    fn join_references() {
+        let base = URL::try_from("gemini://example.com/a/b/c?q").unwrap();
+        let join = |reference: &str| base.join(reference).map(|url| url.to_string());
+
+        assert_eq!(join("d"), Ok("gemini://example.com:1965/a/b/d".to_string()));
+        assert_eq!(join("./d/"), Ok("gemini://example.com:1965/a/b/d/".to_string()));
+        assert_eq!(join("../d?x"), Ok("gemini://example.com:1965/a/d?x".to_string()));
+        assert_eq!(join("../../../d"), Ok("gemini://example.com:1965/d".to_string()));
+        assert_eq!(join(".."), Ok("gemini://example.com:1965/a/".to_string()));
+        assert_eq!(join("/d#part"), Ok("gemini://example.com:1965/d".to_string()));
+        assert_eq!(join("?x"), Ok("gemini://example.com:1965/a/b/c?x".to_string()));
+        assert_eq!(join(""), Ok("gemini://example.com:1965/a/b/c?q".to_string()));
+        assert_eq!(join("//example.org/d"), Ok("gemini://example.org:1965/d".to_string()));
+        assert_eq!(join("gemini://example.org:1966/"), Ok("gemini://example.org:1966/".to_string()));
+        assert!(join("spartan://example.org/").is_err());
+        assert!(join("a b").is_err());
+    }
+
+    #[test]
+    fn other_schemes_use_their_default_ports() {
+        assert_eq!(URL::try_from("http://example.com/").unwrap().to_string(), "http://example.com:80/");
+        assert_eq!(URL::try_from("https://example.com:8443/a").unwrap().to_string(), "https://example.com:8443/a");
+        assert_eq!(URL::try_from("gopher://example.com/1/").unwrap().to_string(), "gopher://example.com:70/1/");
+    }
+
+    #[test]
+    fn path_helpers() {
+        let url = URL::try_from("gemini://example.com/docs/my%20file.tar.gz?q").unwrap();
+        assert_eq!(url.file_name(), Some("my file.tar.gz".to_string()));
+        assert_eq!(url.extension(), Some("gz".to_string()));
+        assert!(!url.is_directory_like());
+        assert_eq!(url.parent().unwrap().to_string(), "gemini://example.com:1965/docs/");
+        assert_eq!(url.parent().unwrap().parent().unwrap().to_string(), "gemini://example.com:1965/");
+        assert_eq!(url.parent().unwrap().parent().unwrap().parent(), None);
+        assert_eq!(url.with_trailing_slash().to_string(), "gemini://example.com:1965/docs/my%20file.tar.gz/?q");
+        assert_eq!(url.without_query().to_string(), "gemini://example.com:1965/docs/my%20file.tar.gz");
+
+        let root = URL::try_from("gemini://example.com").unwrap();
+        assert!(root.is_directory_like());
+        assert_eq!(root.file_name(), None);
+        assert_eq!(root.join_segment("a b/c?").to_string(), "gemini://example.com:1965/a%20b%2Fc%3F");
+        assert_eq!(URL::try_from("gemini://example.com/.hidden").unwrap().extension(), None);
+    }
+
     #[test]
     fn invalid_scheme() {
         let url = URL::try_from("nooo://a.com");